@@ -0,0 +1,75 @@
+//! Latency of `abbr query` against a database of 10k aliases, measured end to
+//! end since that is what a shell waits for. Run with `cargo bench`.
+
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+const ALIASES: usize = 10_000;
+const RUNS: usize = 200;
+
+fn abbr(data_dir: &Path) -> Command {
+  let mut command = Command::new(env!("CARGO_BIN_EXE_abbr"));
+  // Keep the configuration and the trusted files out of the real home
+  // directory, like the tests do.
+  command
+    .env("_ABBR_DATA_DIR", data_dir)
+    .env("HOME", data_dir.join("home"))
+    .env_remove("XDG_CONFIG_HOME")
+    .env_remove("XDG_DATA_HOME");
+  command
+}
+
+fn setup(data_dir: &Path) {
+  let mut json = String::from(r#"{"records":["#);
+  for idx in 0..ALIASES {
+    let separator = if idx == 0 { "" } else { "," };
+    write!(
+      json,
+      r#"{separator}{{"alias":"a{idx}","origin":"echo {idx}","mappings":{{"x":"xargs","l":"ls -la"}}}}"#
+    )
+    .unwrap();
+  }
+  json.push_str("]}");
+
+  // Read from stdin, which doesn't have to be allowed like a file does.
+  let mut child = abbr(data_dir)
+    .args(["import", "--from", "json", "-"])
+    .stdin(Stdio::piped())
+    .stdout(Stdio::null())
+    .spawn()
+    .unwrap();
+  child.stdin.take().unwrap().write_all(json.as_bytes()).unwrap();
+  assert!(child.wait().unwrap().success());
+}
+
+fn bench(data_dir: &Path, alias: &str) {
+  let mut samples = Vec::with_capacity(RUNS);
+  for _ in 0..RUNS {
+    let start = Instant::now();
+    let output = abbr(data_dir).args(["query", alias, "l"]).output().unwrap();
+    samples.push(start.elapsed());
+    assert!(output.status.success());
+  }
+  samples.sort_unstable();
+
+  let percentile = |p: usize| samples[(samples.len() - 1) * p / 100];
+  let mean = samples.iter().sum::<Duration>() / RUNS as u32;
+  println!(
+    "query {alias:<8} mean {mean:>10.2?}  p50 {:>10.2?}  p95 {:>10.2?}",
+    percentile(50),
+    percentile(95)
+  );
+}
+
+fn main() {
+  let dir = tempfile::tempdir().unwrap();
+  setup(dir.path());
+
+  println!("{ALIASES} aliases, {RUNS} runs each");
+  for alias in ["a0", "a5000", "a9999"] {
+    bench(dir.path(), alias);
+  }
+}
@@ -1,7 +1,18 @@
+mod allow;
+mod config;
+mod deny;
 mod edit;
+pub mod export;
+mod history;
+mod import;
 mod init;
+mod list;
 mod new;
-mod query;
+mod profile;
+pub mod query;
+mod redo;
+mod show;
+mod undo;
 
 use anyhow::Result;
 use clap::Parser;
@@ -20,7 +31,29 @@ pub enum Command {
   New(new::New),
   /// Edit command alias
   Edit(edit::Edit),
+  /// Trust a project file, an imported file or an external database
+  Allow(allow::Allow),
+  /// Stop trusting a project file, an imported file or an external database
+  Deny(deny::Deny),
+  /// Get or change settings
+  Config(config::Config),
+  /// Export command aliases
+  Export(export::Export),
+  /// List previous states of the database
+  History(history::History),
+  /// Import command aliases
+  Import(import::Import),
+  /// List command aliases
+  List(list::List),
+  /// Manage profiles, each with its own aliases
+  Profile(profile::Profile),
   Query(query::Query),
+  /// Restore a state undone by `abbr undo`
+  Redo(redo::Redo),
+  /// Show the details of a command alias
+  Show(show::Show),
+  /// Restore a previous state of the database
+  Undo(undo::Undo),
 }
 
 impl Execute for Command {
@@ -29,7 +62,18 @@ impl Execute for Command {
       Command::Init(init) => init.execute(),
       Command::New(new) => new.execute(),
       Command::Edit(edit) => edit.execute(),
+      Command::Allow(allow) => allow.execute(),
+      Command::Deny(deny) => deny.execute(),
+      Command::Config(config) => config.execute(),
+      Command::Export(export) => export.execute(),
+      Command::History(history) => history.execute(),
+      Command::Import(import) => import.execute(),
+      Command::List(list) => list.execute(),
+      Command::Profile(profile) => profile.execute(),
       Command::Query(query) => query.execute(),
+      Command::Redo(redo) => redo.execute(),
+      Command::Show(show) => show.execute(),
+      Command::Undo(undo) => undo.execute(),
     }?;
     Ok(())
   }
@@ -0,0 +1,44 @@
+use crate::command::Execute;
+use crate::error::BrokenPipeHandler;
+use crate::project::{self, Project};
+use crate::trust::Trust;
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Parser)]
+pub struct Allow {
+  /// File to trust, defaults to the closest project file
+  pub path: Option<PathBuf>,
+}
+
+impl Execute for Allow {
+  fn execute(&self) -> Result<()> {
+    let path = source(self.path.as_ref())?;
+    let contents = fs::read(&path)
+      .with_context(|| format!("failed to read file: {}", path.display()))?;
+
+    let mut trust = Trust::open()?;
+    trust.allow(&path, &contents)?;
+    trust.save()?;
+    writeln!(io::stdout(), "allowed {}", path.display()).pipe_exit("stdout")?;
+
+    Ok(())
+  }
+}
+
+/// The file given on the command line, or the closest project file.
+pub fn source(path: Option<&PathBuf>) -> Result<PathBuf> {
+  match path {
+    Some(path) => Ok(path.clone()),
+    None => Project::locate().with_context(|| {
+      format!(
+        "no {} found in the current directory or above",
+        project::FILE_NAME
+      )
+    }),
+  }
+}
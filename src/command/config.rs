@@ -0,0 +1,91 @@
+use crate::command::Execute;
+use crate::config::{self, Key};
+use crate::error::BrokenPipeHandler;
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use std::env;
+use std::io::{self, Write};
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Subcommand)]
+#[command(disable_help_subcommand = true)]
+pub enum Action {
+  /// Print the value of a setting
+  Get {
+    #[arg(value_enum)]
+    key: Key,
+  },
+  /// Change a setting in the configuration file
+  Set {
+    #[arg(value_enum)]
+    key: Key,
+    value: String,
+  },
+  /// Reset a setting in the configuration file to its default
+  Unset {
+    #[arg(value_enum)]
+    key: Key,
+  },
+  /// List all settings
+  List,
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Parser)]
+pub struct Config {
+  #[clap(subcommand)]
+  /// Action to perform
+  pub action: Action,
+}
+
+impl Execute for Config {
+  fn execute(&self) -> Result<()> {
+    let handle = &mut io::stdout();
+    match self.action {
+      Action::Get { key } => {
+        let value = config::Config::load()?.get(key)?;
+        writeln!(handle, "{value}").pipe_exit("stdout")?;
+      }
+      Action::Set { key, ref value } => {
+        let path = config::config_path()?;
+        let mut config = config::Config::read(&path)?;
+        config.set(key, value)?;
+        config.write(&path)?;
+        warn_overridden(key);
+      }
+      Action::Unset { key } => {
+        let path = config::config_path()?;
+        let mut config = config::Config::read(&path)?;
+        config.unset(key);
+        config.write(&path)?;
+        warn_overridden(key);
+      }
+      Action::List => {
+        let config = config::Config::load()?;
+        for key in Key::ALL {
+          let value = config.get(key)?;
+          let line = match env::var_os(key.env()) {
+            Some(_) => {
+              format!("{} = {value}  # from {}", key.name(), key.env())
+            }
+            None => format!("{} = {value}", key.name()),
+          };
+          writeln!(handle, "{line}").pipe_exit("stdout")?;
+        }
+      }
+    }
+
+    Ok(())
+  }
+}
+
+pub fn warn_overridden(key: Key) {
+  if env::var_os(key.env()).is_some() {
+    _ = writeln!(
+      io::stderr(),
+      "abbr: {} is overridden by {}, which is still set",
+      key.name(),
+      key.env()
+    );
+  }
+}
@@ -0,0 +1,28 @@
+use crate::command::allow;
+use crate::command::Execute;
+use crate::error::BrokenPipeHandler;
+use crate::trust::Trust;
+use anyhow::Result;
+use clap::Parser;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Parser)]
+pub struct Deny {
+  /// File to stop trusting, defaults to the closest project file
+  pub path: Option<PathBuf>,
+}
+
+impl Execute for Deny {
+  fn execute(&self) -> Result<()> {
+    let path = allow::source(self.path.as_ref())?;
+
+    let mut trust = Trust::open()?;
+    trust.deny(&path)?;
+    trust.save()?;
+    writeln!(io::stdout(), "denied {}", path.display()).pipe_exit("stdout")?;
+
+    Ok(())
+  }
+}
@@ -1,17 +1,29 @@
 use crate::command::Execute;
 use crate::database::{Database, DbOperation, EXCLUDED_CHARS};
+use crate::error::BrokenPipeHandler;
 use crate::util;
-use anyhow::Result;
+use anyhow::{bail, ensure, Result};
 use clap::{Parser, Subcommand};
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
 
 #[cfg_attr(debug_assertions, derive(Debug))]
 #[derive(Clone, Subcommand)]
 #[command(disable_help_subcommand = true)]
 pub enum Action {
-  /// Add new parameter alias(es), format: `<origin>=<alias>`
+  /// Add new parameter alias(es), format: `<origin>=<alias>`, or
+  /// `<origin>=<word>.<alias>` to only map `<alias>` after `<word>`
   Add { params: Vec<String> },
   /// Remove parameter alias(es)
   Rmv { params: Vec<String> },
+  /// Set default argument(s), or clear them if none are given
+  Def { defaults: Vec<String> },
+  /// Expand combined single-letter parameters, such as `-aq`, letter by
+  /// letter, or stop with `--off`
+  Cmb {
+    #[arg(long)]
+    off: bool,
+  },
   /// Remove command alias
   Del,
 }
@@ -27,28 +39,269 @@ pub struct Edit {
   pub action: Action,
 }
 
+/// What happened to the alias, or to a single parameter of it.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Eq)]
+pub enum Change {
+  Added { param: String, origin: String },
+  Replaced { param: String, origin: String, previous: String },
+  Unchanged { param: String },
+  Removed { param: String },
+  NotFound { param: String },
+  Defaults { defaults: Vec<String> },
+  CombinedFlags { enabled: bool },
+  Deleted { alias: String },
+}
+
+impl Change {
+  fn is_change(&self) -> bool {
+    !matches!(self, Change::Unchanged { .. } | Change::NotFound { .. })
+  }
+}
+
+impl Display for Change {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Change::Added { param, origin } => {
+        write!(f, "added     {param}: {origin}{}", scope_note(param))
+      }
+      Change::Replaced { param, origin, previous } => {
+        let note = scope_note(param);
+        write!(f, "replaced  {param}: {origin}{note} (was: {previous})")
+      }
+      Change::Unchanged { param } => write!(f, "unchanged {param}"),
+      Change::Removed { param } => write!(f, "removed   {param}"),
+      Change::NotFound { param } => write!(f, "not found {param}"),
+      Change::Defaults { defaults } if defaults.is_empty() => {
+        write!(f, "cleared default arguments")
+      }
+      Change::Defaults { defaults } => {
+        write!(f, "default arguments: {}", defaults.join(" "))
+      }
+      Change::CombinedFlags { enabled: true } => {
+        write!(f, "combined flags are expanded")
+      }
+      Change::CombinedFlags { enabled: false } => {
+        write!(f, "combined flags are passed through")
+      }
+      Change::Deleted { alias } => write!(f, "deleted   {alias}"),
+    }
+  }
+}
+
+/// Points out that a parameter is scoped, since a dot that was meant to be
+/// part of it has to be escaped.
+fn scope_note(param: &str) -> String {
+  match util::param_to_scope(param) {
+    (Some(scope), _) => format!(" (scoped to `{scope}`)"),
+    (None, _) => String::new(),
+  }
+}
+
+/// Splits `<origin>=<alias>` parameters into pairs, refusing the ones that
+/// can't be stored.
+pub fn parse_params(params: &[String]) -> Result<Vec<(&str, &str)>> {
+  let mut mappings = Vec::with_capacity(params.len());
+  for param in params {
+    if param.contains(EXCLUDED_CHARS) {
+      bail!("parameter `{param}` spans multiple lines");
+    }
+    let (origin, param) = util::alias_to_pair(param)?;
+    if let (Some(scope), _) = util::param_to_scope(param) {
+      ensure!(
+        util::scope_words(scope).iter().all(|word| !word.is_empty()),
+        "parameter `{param}` is scoped to an empty word"
+      );
+    }
+    mappings.push((origin, param));
+  }
+  Ok(mappings)
+}
+
 impl Execute for Edit {
   fn execute(&self) -> Result<()> {
     let mut db = Database::open()?;
+    let changes = self.edit(&mut db)?;
+    if changes.iter().any(Change::is_change) {
+      db.save()?;
+    }
 
-    match &self.action {
+    let handle = &mut io::stdout();
+    for change in changes.iter() {
+      writeln!(handle, "{change}").pipe_exit("stdout")?;
+    }
+
+    Ok(())
+  }
+}
+
+impl Edit {
+  fn edit(&self, db: &mut impl DbOperation) -> Result<Vec<Change>> {
+    let alias = self.alias.as_str();
+    let Some(record) = db.records().iter().find(|r| r.alias == alias) else {
+      let aliases = db.records().iter().map(|r| r.alias.as_ref());
+      let hint = util::did_you_mean(&util::closest(alias, aliases));
+      bail!("alias `{alias}` not found{hint}");
+    };
+    let previous = |param: &str| {
+      record
+        .mappings
+        .iter()
+        .find(|(alias, _)| alias == param)
+        .map(|(_, origin)| origin.to_string())
+    };
+
+    let changes = match &self.action {
       Action::Add { params } => {
-        let mut mappings = Vec::with_capacity(params.len());
-        for param in params {
-          if param.contains(EXCLUDED_CHARS) {
-            continue;
-          }
-          let (origin, alias) = util::alias_to_pair(param)?;
-          mappings.push((origin, alias));
+        let mappings = parse_params(params)?;
+        let mut changes = Vec::with_capacity(params.len());
+        for &(origin, param) in mappings.iter() {
+          let (param, origin) = (param.to_owned(), origin.to_owned());
+          changes.push(match previous(&param) {
+            None => Change::Added { param, origin },
+            Some(previous) if previous == origin => Change::Unchanged { param },
+            Some(previous) => Change::Replaced { param, origin, previous },
+          });
         }
-        db.add_params(self.alias.as_str(), mappings.into_iter())
+        db.add_params(alias, mappings.into_iter());
+        changes
       }
       Action::Rmv { params } => {
-        db.rem_params(self.alias.as_str(), params.iter().map(String::as_str))
+        let changes = params
+          .iter()
+          .map(|param| match previous(param) {
+            Some(_) => Change::Removed { param: param.clone() },
+            None => Change::NotFound { param: param.clone() },
+          })
+          .collect();
+        db.rem_params(alias, params.iter().map(String::as_str));
+        changes
       }
-      Action::Del => db.del_record(self.alias.as_str()),
-    }
+      Action::Def { defaults } => {
+        db.set_defaults(alias, defaults.iter().map(String::as_str));
+        vec![Change::Defaults { defaults: defaults.clone() }]
+      }
+      Action::Cmb { off } => {
+        db.set_combined_flags(alias, !off);
+        vec![Change::CombinedFlags { enabled: !off }]
+      }
+      Action::Del => {
+        db.del_record(alias);
+        vec![Change::Deleted { alias: alias.to_owned() }]
+      }
+    };
 
-    Ok(())
+    Ok(changes)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::command::edit::{Action, Change, Edit};
+  use crate::database::{test::DummyDatabase, DbOperation};
+
+  fn edit(alias: &str, action: Action) -> (DummyDatabase, Vec<Change>) {
+    let mut db = DummyDatabase::open().unwrap();
+    let edit = Edit { alias: alias.to_owned(), action };
+    let changes = edit.edit(&mut db).unwrap();
+    (db, changes)
+  }
+
+  #[test]
+  fn test_edit_add() {
+    let params = ["compose=cmp", "container=ct", "ls=ls"];
+    let action =
+      Action::Add { params: params.iter().map(|p| p.to_string()).collect() };
+    let (db, changes) = edit("dk", action);
+
+    assert_eq!(
+      changes,
+      vec![
+        Change::Unchanged { param: "cmp".to_string() },
+        Change::Added {
+          param: "ct".to_string(),
+          origin: "container".to_string()
+        },
+        Change::Added { param: "ls".to_string(), origin: "ls".to_string() },
+      ]
+    );
+    let record = db.records().iter().find(|r| r.alias == "dk").unwrap();
+    assert!(record.mappings.contains(&("ct".into(), "container".into())));
+  }
+
+  #[test]
+  fn test_edit_replace_and_remove() {
+    let action = Action::Add { params: vec!["lst=l".to_string()] };
+    let (_, changes) = edit("dk", action);
+    assert_eq!(
+      changes,
+      vec![Change::Replaced {
+        param: "l".to_string(),
+        origin: "lst".to_string(),
+        previous: "ls".to_string()
+      }]
+    );
+
+    let action = Action::Rmv { params: vec!["i".into(), "x".into()] };
+    let (db, changes) = edit("dk", action);
+    assert_eq!(
+      changes,
+      vec![
+        Change::Removed { param: "i".to_string() },
+        Change::NotFound { param: "x".to_string() },
+      ]
+    );
+    let record = db.records().iter().find(|r| r.alias == "dk").unwrap();
+    assert_eq!(record.mappings.len(), 3);
+  }
+
+  #[test]
+  fn test_edit_scoped() {
+    let action = Action::Add {
+      params: vec!["logs=compose.l".to_string(), "--latest=logs.l".to_string()],
+    };
+    let (db, changes) = edit("dk", action);
+    assert_eq!(
+      changes[0],
+      Change::Added {
+        param: "compose.l".to_string(),
+        origin: "logs".to_string()
+      }
+    );
+    let record = db.records().iter().find(|r| r.alias == "dk").unwrap();
+    assert!(record.mappings.contains(&("logs.l".into(), "--latest".into())));
+
+    let mut db = DummyDatabase::open().unwrap();
+    let action = Action::Add { params: vec!["logs=compose..l".to_string()] };
+    let edit = Edit { alias: "dk".to_owned(), action };
+    let err = edit.edit(&mut db).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "parameter `compose..l` is scoped to an empty word"
+    );
+  }
+
+  #[test]
+  fn test_edit_combined_flags() {
+    let (mut db, changes) = edit("dk", Action::Cmb { off: false });
+    assert_eq!(changes, vec![Change::CombinedFlags { enabled: true }]);
+    let record = db.records().iter().find(|r| r.alias == "dk").unwrap();
+    assert!(record.combined_flags);
+
+    let edit =
+      Edit { alias: "dk".to_owned(), action: Action::Cmb { off: true } };
+    edit.edit(&mut db).unwrap();
+    let record = db.records().iter().find(|r| r.alias == "dk").unwrap();
+    assert!(!record.combined_flags);
+  }
+
+  #[test]
+  fn test_edit_missing_alias() {
+    let mut db = DummyDatabase::open().unwrap();
+    let edit = Edit { alias: "dks".to_owned(), action: Action::Del };
+    let err = edit.edit(&mut db).unwrap_err();
+
+    assert_eq!(err.to_string(), "alias `dks` not found, did you mean `dk`?");
+    assert_eq!(db.records().len(), 4);
   }
 }
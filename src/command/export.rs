@@ -0,0 +1,638 @@
+use crate::command::query::{self, Part};
+use crate::command::Execute;
+use crate::database::{Database, DbOperation, Record, EXCLUDED_CHARS};
+use crate::error::BrokenPipeHandler;
+use crate::shell::{self, ExportFish, ExportPosix, Function, Piece};
+use crate::util;
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+use hashbrown::HashSet;
+use rinja::Template;
+use serde::de::{self, DeserializeSeed, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::{self, Formatter};
+use std::io::{self, Write};
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(ValueEnum, Clone)]
+#[value(rename_all = "kebab-case")]
+pub enum ExportFormat {
+  /// Standalone bash functions
+  Bash,
+  /// Standalone fish functions
+  Fish,
+  Json,
+  Toml,
+  /// Standalone zsh functions
+  Zsh,
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Parser)]
+pub struct Export {
+  /// Format to print the database in
+  #[arg(long, value_enum)]
+  pub format: ExportFormat,
+}
+
+impl Execute for Export {
+  fn execute(&self) -> Result<()> {
+    let db = Database::open_readonly()?;
+    let source = match self.format {
+      ExportFormat::Bash => to_posix(db.records(), "bash")?,
+      ExportFormat::Fish => to_fish(db.records())?,
+      ExportFormat::Json => to_json(db.records())?,
+      ExportFormat::Toml => to_toml(db.records())?,
+      ExportFormat::Zsh => to_posix(db.records(), "zsh")?,
+    };
+    writeln!(io::stdout(), "{}", source.trim_end()).pipe_exit("stdout")?;
+    Ok(())
+  }
+}
+
+/// Human-editable form of the database, shared by `abbr export` and
+/// `abbr import`.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Document {
+  #[serde(default, deserialize_with = "unique_entries")]
+  records: Vec<Entry>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct Entry {
+  alias: Word,
+  origin: Word,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  defaults: Vec<Word>,
+  /// Parameter aliases, keyed by alias like [`Record::mappings`].
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  mappings: BTreeMap<Word, Word>,
+  #[serde(default, skip_serializing_if = "is_false")]
+  combined_flags: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+  !value
+}
+
+/// A non-empty, single line string. Validating it while deserializing lets the
+/// parser report the line of an invalid value.
+#[derive(Serialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+#[serde(transparent)]
+struct Word(String);
+
+impl<'de> Deserialize<'de> for Word {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    struct WordVisitor;
+
+    impl Visitor<'_> for WordVisitor {
+      type Value = Word;
+
+      fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("a string")
+      }
+
+      fn visit_str<E>(self, word: &str) -> Result<Self::Value, E>
+      where
+        E: de::Error,
+      {
+        if word.is_empty() {
+          return Err(E::custom("value must not be empty"));
+        }
+        if word.contains(EXCLUDED_CHARS) {
+          return Err(E::custom(format!(
+            "value `{}` must not contain line breaks",
+            word.escape_debug()
+          )));
+        }
+        Ok(Word(word.to_owned()))
+      }
+    }
+
+    deserializer.deserialize_str(WordVisitor)
+  }
+}
+
+fn unique_entries<'de, D>(deserializer: D) -> Result<Vec<Entry>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  struct UniqueEntry<'a>(&'a mut HashSet<Word>);
+
+  impl<'de> DeserializeSeed<'de> for UniqueEntry<'_> {
+    type Value = Entry;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+      D: Deserializer<'de>,
+    {
+      let entry = Entry::deserialize(deserializer)?;
+      if !self.0.insert(entry.alias.clone()) {
+        return Err(de::Error::custom(format!(
+          "duplicate alias `{}`",
+          entry.alias.0
+        )));
+      }
+      Ok(entry)
+    }
+  }
+
+  struct EntriesVisitor;
+
+  impl<'de> Visitor<'de> for EntriesVisitor {
+    type Value = Vec<Entry>;
+
+    fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+      f.write_str("a list of records")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+      A: SeqAccess<'de>,
+    {
+      let mut entries = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+      let mut aliases = HashSet::new();
+      while let Some(entry) =
+        seq.next_element_seed(UniqueEntry(&mut aliases))?
+      {
+        entries.push(entry);
+      }
+      Ok(entries)
+    }
+  }
+
+  deserializer.deserialize_seq(EntriesVisitor)
+}
+
+impl Document {
+  fn from_records(records: &[Record]) -> Self {
+    let mut records = records
+      .iter()
+      .map(|record| Entry {
+        alias: Word(record.alias.to_string()),
+        origin: Word(record.origin.to_string()),
+        mappings: record
+          .mappings
+          .iter()
+          .map(|(alias, origin)| {
+            (Word(alias.to_string()), Word(origin.to_string()))
+          })
+          .collect(),
+        defaults: record
+          .defaults
+          .iter()
+          .map(|default| Word(default.to_string()))
+          .collect(),
+        combined_flags: record.combined_flags,
+      })
+      .collect::<Vec<_>>();
+    records.sort_unstable_by(|a, b| a.alias.cmp(&b.alias));
+    Self { records }
+  }
+
+  fn into_records(self) -> Vec<Record<'static>> {
+    self
+      .records
+      .into_iter()
+      .map(|entry| Record {
+        origin: entry.origin.0.into(),
+        alias: entry.alias.0.into(),
+        mappings: entry
+          .mappings
+          .into_iter()
+          .map(|(alias, origin)| (alias.0.into(), origin.0.into()))
+          .collect(),
+        defaults: entry.defaults.into_iter().map(|d| d.0.into()).collect(),
+        combined_flags: entry.combined_flags,
+      })
+      .collect()
+  }
+}
+
+pub fn to_json(records: &[Record]) -> Result<String> {
+  serde_json::to_string_pretty(&Document::from_records(records))
+    .context("failed to serialize database to JSON")
+}
+
+pub fn to_toml(records: &[Record]) -> Result<String> {
+  toml::to_string_pretty(&Document::from_records(records))
+    .context("failed to serialize database to TOML")
+}
+
+/// Splits records into the ones that can be exported as shell functions, with
+/// their references to other aliases resolved, and the aliases that can't be
+/// along with the reason.
+fn exportable<'a>(
+  records: &'a [Record<'a>],
+) -> (Vec<Cow<'a, Record<'a>>>, Vec<(&'a str, String)>) {
+  let lookup =
+    |alias: &str| Ok(records.iter().find(|r| r.alias == alias).cloned());
+  let mut valid = Vec::with_capacity(records.len());
+  let mut skipped = Vec::new();
+  for record in records {
+    if !is_function_name(&record.alias) {
+      skipped.push((record.alias.as_ref(), "not a valid function name".into()));
+      continue;
+    }
+    match query::resolve(record, lookup) {
+      Ok(resolved) => match unsupported(&resolved) {
+        Some(reason) => skipped.push((record.alias.as_ref(), reason)),
+        None => valid.push(resolved),
+      },
+      Err(e) => skipped.push((record.alias.as_ref(), e.to_string())),
+    }
+  }
+  valid.sort_unstable_by(|a, b| a.alias.cmp(&b.alias));
+  skipped.sort_unstable_by_key(|(alias, _)| *alias);
+  (valid, skipped)
+}
+
+/// Why the functions can't expand `record` like `abbr query`, if they can't.
+fn unsupported(record: &Record) -> Option<String> {
+  if record.combined_flags {
+    return Some("combined flags aren't supported".into());
+  }
+  record.mappings.iter().find_map(|(alias, _)| {
+    match util::param_to_scope(alias) {
+      (Some(_), _) => {
+        Some(format!("scoped parameter `{alias}` isn't supported"))
+      }
+      (None, key) if key.split_whitespace().nth(1).is_some() => Some(format!(
+        "parameter `{alias}` spanning several words isn't supported"
+      )),
+      _ => None,
+    }
+  })
+}
+
+/// Turns records into shell functions, quoted with `quote`.
+fn to_functions<'a>(
+  records: &'a [Cow<'_, Record<'_>>],
+  quote: fn(&str) -> Cow<'_, str>,
+) -> Vec<Function<'a>> {
+  records
+    .iter()
+    .map(|record| {
+      let mut mappings = record.mappings.iter().collect::<Vec<_>>();
+      mappings.sort_unstable();
+      let mut names = Vec::new();
+      let mut used = Vec::new();
+      let template = query::parse_template(&record.origin)
+        .into_iter()
+        .map(|part| match part {
+          Part::Text(text) => Piece::Text(quote(text)),
+          Part::Index(idx) => {
+            if !used.contains(&idx) {
+              used.push(idx);
+            }
+            Piece::Index(idx)
+          }
+          Part::Name(name) => match names.iter().position(|n| *n == name) {
+            Some(idx) => Piece::Name(idx),
+            None => {
+              names.push(name);
+              Piece::Name(names.len() - 1)
+            }
+          },
+        })
+        .collect();
+      Function {
+        name: &record.alias,
+        command: &record.origin,
+        template,
+        names,
+        used,
+        mappings: mappings
+          .into_iter()
+          .map(|(alias, origin)| {
+            // A dot escaped as `\.` is typed without the escape.
+            let alias = util::unescape_scope(alias);
+            (quote(&alias).into_owned().into(), quote(origin))
+          })
+          .collect(),
+        defaults: (0..record.defaults.len())
+          .map(|idx| {
+            quote(&record.defaults[idx..].join(" ")).into_owned().into()
+          })
+          .collect(),
+      }
+    })
+    .collect()
+}
+
+fn is_function_name(name: &str) -> bool {
+  !name.starts_with('-')
+    && name.chars().all(|c| {
+      c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | ':' | '+')
+    })
+}
+
+pub fn to_posix(records: &[Record], shell: &str) -> Result<String> {
+  let (records, skipped) = exportable(records);
+  let functions = to_functions(&records, shell::quote_posix);
+  ExportPosix { shell, functions: &functions, skipped: &skipped }
+    .render()
+    .context("failed to render template")
+}
+
+pub fn to_fish(records: &[Record]) -> Result<String> {
+  let (records, skipped) = exportable(records);
+  let functions = to_functions(&records, shell::quote_fish);
+  ExportFish { functions: &functions, skipped: &skipped }
+    .render()
+    .context("failed to render template")
+}
+
+pub fn from_json(contents: &str) -> Result<Vec<Record<'static>>> {
+  let document = serde_json::from_str::<Document>(contents)
+    .context("failed to parse JSON")?;
+  Ok(document.into_records())
+}
+
+pub fn from_toml(contents: &str) -> Result<Vec<Record<'static>>> {
+  let document =
+    toml::from_str::<Document>(contents).context("failed to parse TOML")?;
+  Ok(document.into_records())
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::command::export::{
+    from_json, from_toml, to_fish, to_json, to_posix, to_toml,
+  };
+  use crate::database::{test::DummyDatabase, DbOperation, Record};
+  use std::io;
+  use std::process::{Command, Output};
+
+  type Flat = (String, String, Vec<(String, String)>, Vec<String>);
+
+  fn sorted(records: &[Record]) -> Vec<Flat> {
+    let mut records = records
+      .iter()
+      .map(|record| {
+        let mut mappings = record
+          .mappings
+          .iter()
+          .map(|(alias, origin)| (alias.to_string(), origin.to_string()))
+          .collect::<Vec<_>>();
+        mappings.sort_unstable();
+        let defaults = record.defaults.iter().map(|d| d.to_string()).collect();
+        (
+          record.alias.to_string(),
+          record.origin.to_string(),
+          mappings,
+          defaults,
+        )
+      })
+      .collect::<Vec<_>>();
+    records.sort_unstable();
+    records
+  }
+
+  #[test]
+  fn test_round_trip_toml() {
+    let mut db = DummyDatabase::open().unwrap();
+    db.set_defaults("e", ["hello", "world"].into_iter());
+    db.set_combined_flags("dk", true);
+    let source = to_toml(db.records()).unwrap();
+
+    assert!(source.contains("[records.mappings]\ncmp = \"compose\"\n"));
+    assert!(source.contains("combined-flags = true\n"));
+    let records = from_toml(&source).unwrap();
+    assert_eq!(sorted(&records), sorted(db.records()));
+    let dk = records.iter().find(|r| r.alias == "dk").unwrap();
+    assert!(dk.combined_flags);
+  }
+
+  #[test]
+  fn test_round_trip_json() {
+    let mut db = DummyDatabase::open().unwrap();
+    db.set_defaults("e", ["hello", "world"].into_iter());
+    let source = to_json(db.records()).unwrap();
+
+    assert_eq!(sorted(&from_json(&source).unwrap()), sorted(db.records()));
+  }
+
+  #[test]
+  fn test_invalid_line() {
+    let toml = "[[records]]\nalias = \"e\"\norigin = \"echo\"\n\n\
+                [[records]]\nalias = \"\"\norigin = \"ls\"\n";
+    let err = format!("{:#}", from_toml(toml).unwrap_err());
+    assert!(err.contains("line 6"), "{err}");
+    assert!(err.contains("value must not be empty"), "{err}");
+
+    let json = "{\n  \"records\": [\n    { \"alias\": \"e\", \"origin\": \"echo\" },\n    \
+                { \"alias\": \"l\", \"origin\": \"\\n\" }\n  ]\n}";
+    let err = format!("{:#}", from_json(json).unwrap_err());
+    assert!(err.contains("line 4"), "{err}");
+    assert!(err.contains("must not contain line breaks"), "{err}");
+
+    let json = r#"{ "records": [{ "alias": "e", "origin": "echo" },
+                                { "alias": "e", "origin": "ls" }] }"#;
+    let err = format!("{:#}", from_json(json).unwrap_err());
+    assert!(err.contains("duplicate alias `e`"), "{err}");
+  }
+
+  #[test]
+  fn test_bash_functions() {
+    let mut db = DummyDatabase::open().unwrap();
+    db.set_defaults("gtd", ["v0", "v00"].into_iter());
+    let functions = to_posix(db.records(), "bash").unwrap();
+
+    // Stub the aliased commands so each prints its NUL-terminated arguments.
+    let script = format!(
+      "for cmd in cargo docker echo git; do \
+         eval \"$cmd() {{ printf '%s\\0' $cmd \\\"\\$@\\\"; printf '\\n'; }}\"; \
+       done\n\
+       {functions}\n\
+       ca a/3 serde tokio clap; \
+       dk cmp ud 'my service' '*'; \
+       e i/1 '$HOME' \"it's\"; \
+       ca a/* serde -F; \
+       e 'a\\/3' src/lib/1 x; \
+       gtd v1; gtd"
+    );
+    let output = Command::new("bash").arg("-c").arg(script).output().unwrap();
+    assert!(output.status.success());
+
+    // Separate the arguments with `|` to compare them at a glance.
+    let stdout = String::from_utf8(output.stdout).unwrap().replace('\0', "|");
+    assert_eq!(
+      stdout.lines().collect::<Vec<_>>(),
+      vec![
+        "cargo|add|serde|tokio|clap|",
+        "docker|compose|up|-d|my service|*|",
+        "echo|i|$HOME|it's|",
+        "cargo|add|serde|-F|",
+        "echo|a/3|src/lib|x|",
+        "git|tag|-d|v1|v00|",
+        "git|tag|-d|v0|v00|",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_bash_placeholders() {
+    let mut db = DummyDatabase { records: Vec::new() };
+    db.add_record(Record {
+      origin: "git log --oneline {1}..{2}".into(),
+      alias: "glr".into(),
+      mappings: vec![("m".into(), "main".into())],
+      defaults: Vec::new(),
+      combined_flags: false,
+    });
+    db.add_record(Record {
+      origin: "kubectl -n {ns} logs".into(),
+      alias: "kl".into(),
+      mappings: Vec::new(),
+      defaults: vec!["api-0".into()],
+      combined_flags: false,
+    });
+    db.add_record(Record {
+      origin: "git show HEAD@{{1}}".into(),
+      alias: "gsh".into(),
+      mappings: Vec::new(),
+      defaults: Vec::new(),
+      combined_flags: false,
+    });
+    let functions = to_posix(db.records(), "bash").unwrap();
+
+    let script = format!(
+      "for cmd in git kubectl; do \
+         eval \"$cmd() {{ printf '%s\\0' $cmd \\\"\\$@\\\"; printf '\\n'; }}\"; \
+       done\n\
+       {functions}\n\
+       glr m 'my branch' -p; \
+       glr v1; \
+       kl ns=prod; \
+       kl web ns=prod ns=dev; \
+       kl; \
+       gsh --stat"
+    );
+    let output = Command::new("bash").arg("-c").arg(script).output().unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap().replace('\0', "|");
+    assert_eq!(
+      stdout.lines().collect::<Vec<_>>(),
+      vec![
+        "git|log|--oneline|main..my branch|-p|",
+        "kubectl|-n|prod|logs|api-0|",
+        "kubectl|-n|prod|logs|web|ns=dev|",
+        "git|show|HEAD@{1}|--stat|",
+      ]
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(
+      stderr.lines().collect::<Vec<_>>(),
+      vec![
+        "abbr: alias `glr` requires argument {2}",
+        "abbr: alias `kl` requires `ns=<value>` for {ns}",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_bash_references() {
+    let mut db = DummyDatabase::open().unwrap();
+    for (alias, origin) in [("dc", "@dk cmp"), ("bad", "@missing")] {
+      db.add_record(Record {
+        origin: origin.into(),
+        alias: alias.into(),
+        mappings: vec![("b".into(), "build".into())],
+        defaults: Vec::new(),
+        combined_flags: false,
+      });
+    }
+    let functions = to_posix(db.records(), "bash").unwrap();
+
+    assert!(functions.contains(
+      "# -- skipped `bad`: alias `bad` references `missing`, which doesn't exist --"
+    ));
+    assert!(functions.contains("# dc: docker compose\n"));
+    let script = format!(
+      "docker() {{ printf '%s\\0' docker \"$@\"; }}\n{functions}\ndc ud b"
+    );
+    let output = Command::new("bash").arg("-c").arg(script).output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap().replace('\0', "|");
+    assert_eq!(stdout, "docker|compose|up|-d|build|");
+  }
+
+  /// Runs `script` in `shell`, or returns `None` if it isn't installed.
+  fn run(shell: &str, script: &str) -> Option<Output> {
+    match Command::new(shell).arg("-c").arg(script).output() {
+      Ok(output) => Some(output),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+      Err(e) => panic!("failed to run {shell}: {e}"),
+    }
+  }
+
+  #[test]
+  fn test_group_arity() {
+    let db = DummyDatabase::open().unwrap();
+    let posix = to_posix(db.records(), "bash").unwrap();
+    let zsh = to_posix(db.records(), "zsh").unwrap();
+    let fish = to_fish(db.records()).unwrap();
+    let scripts = [
+      ("bash", format!("cargo() {{ echo cargo \"$@\"; }}\n{posix}")),
+      ("zsh", format!("cargo() {{ echo cargo \"$@\"; }}\n{zsh}")),
+      ("fish", format!("function cargo; echo cargo $argv; end\n{fish}")),
+    ];
+
+    for (shell, functions) in scripts {
+      let Some(output) = run(shell, &format!("{functions}\nca a/5 serde"))
+      else {
+        continue;
+      };
+      assert_eq!(output.status.code(), Some(1), "{shell}");
+      assert!(output.stdout.is_empty(), "{shell}");
+      assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "abbr: `a/5` takes 5 arguments, got 1\n",
+        "{shell}"
+      );
+
+      let script = format!("{functions}\nca a/1; ca a/1 serde");
+      let output = run(shell, &script).unwrap();
+      assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "abbr: `a/1` takes 1 argument, got 0\n",
+        "{shell}"
+      );
+      assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "cargo add serde\n",
+        "{shell}"
+      );
+    }
+  }
+
+  #[test]
+  fn test_unsupported_functions() {
+    let mut db = DummyDatabase::open().unwrap();
+    db.set_combined_flags("ca", true);
+    db.add_params("dk", [("logs", "compose.l")].into_iter());
+    db.add_params("gtd", [("v1", "a b")].into_iter());
+    db.add_params("e", [("Dockerfile.dev", r"df\.dev")].into_iter());
+    let functions = to_posix(db.records(), "bash").unwrap();
+
+    for skipped in [
+      "# -- skipped `ca`: combined flags aren't supported --",
+      "# -- skipped `dk`: scoped parameter `compose.l` isn't supported --",
+      "# -- skipped `gtd`: parameter `a b` spanning several words isn't supported --",
+    ] {
+      assert!(functions.contains(skipped), "{functions}");
+    }
+    let script = format!("{functions}\ne df.dev");
+    let output = Command::new("bash").arg("-c").arg(script).output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "Dockerfile.dev\n");
+  }
+}
@@ -0,0 +1,109 @@
+use crate::command::Execute;
+use crate::database::{Database, DbOperation, Record, Snapshot};
+use crate::error::BrokenPipeHandler;
+use anyhow::Result;
+use clap::Parser;
+use std::io::{self, Write};
+use std::time::SystemTime;
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Parser)]
+pub struct History {}
+
+impl Execute for History {
+  fn execute(&self) -> Result<()> {
+    let db = Database::open_readonly()?;
+    let history = db.history()?;
+    let handle = &mut io::stdout();
+    if history.is_empty() {
+      writeln!(handle, "no previous states").pipe_exit("stdout")?;
+    }
+
+    let position = db.position(&history)?;
+    for (idx, snapshot) in history.iter().enumerate() {
+      // Numbered by the steps to `abbr undo`, or to `abbr redo` for the states
+      // that were undone.
+      let steps = match position {
+        Some(pos) if idx == pos => continue,
+        Some(pos) if idx < pos => format!("r{}", pos - idx),
+        Some(pos) => (idx - pos).to_string(),
+        None => (idx + 1).to_string(),
+      };
+      let bytes = snapshot.read()?;
+      let records = Snapshot::records(&bytes)?;
+      writeln!(
+        handle,
+        "{:>2}  {:<16}  {:>3} {:<7}  {}",
+        steps,
+        time_ago(snapshot.time),
+        records.len(),
+        if records.len() == 1 { "alias" } else { "aliases" },
+        diff(db.records(), &records).join(" ")
+      )
+      .pipe_exit("stdout")?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Lists what restoring `previous` would change: `+` for aliases it brings
+/// back, `-` for aliases it removes and `~` for aliases it changes.
+fn diff(current: &[Record], previous: &[Record]) -> Vec<String> {
+  let mut changes = Vec::new();
+  for record in previous {
+    match current.iter().find(|r| r.alias == record.alias) {
+      None => changes.push(format!("+{}", record.alias)),
+      Some(r) if !r.same_as(record) => changes.push(format!("~{}", r.alias)),
+      Some(_) => {}
+    }
+  }
+  for record in current {
+    if !previous.iter().any(|r| r.alias == record.alias) {
+      changes.push(format!("-{}", record.alias));
+    }
+  }
+  changes.sort_unstable_by(|a, b| a[1..].cmp(&b[1..]));
+  changes
+}
+
+pub fn time_ago(time: SystemTime) -> String {
+  let secs = time.elapsed().unwrap_or_default().as_secs();
+  let (amount, unit) = match secs {
+    0..60 => return "just now".to_owned(),
+    60..3600 => (secs / 60, "minute"),
+    3600..86400 => (secs / 3600, "hour"),
+    _ => (secs / 86400, "day"),
+  };
+  let plural = if amount == 1 { "" } else { "s" };
+  format!("{amount} {unit}{plural} ago")
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::command::history::{diff, time_ago};
+  use crate::database::{test::DummyDatabase, DbOperation};
+  use std::time::{Duration, SystemTime};
+
+  #[test]
+  fn test_diff() {
+    let previous = DummyDatabase::open().unwrap();
+    let mut current = DummyDatabase::open().unwrap();
+    current.del_record("e");
+    current.add_params("dk", [("build", "b")].into_iter());
+    current.set_defaults("gtd", ["v1"].into_iter());
+    current.set_defaults("gtd", std::iter::empty());
+
+    assert_eq!(diff(current.records(), previous.records()), vec!["~dk", "+e"]);
+  }
+
+  #[test]
+  fn test_time_ago() {
+    let ago = |secs| time_ago(SystemTime::now() - Duration::from_secs(secs));
+
+    assert_eq!(ago(5), "just now");
+    assert_eq!(ago(60), "1 minute ago");
+    assert_eq!(ago(7200), "2 hours ago");
+    assert_eq!(ago(3 * 86400), "3 days ago");
+  }
+}
@@ -0,0 +1,326 @@
+use crate::command::{export, Execute};
+use crate::database::{Database, DbOperation, Record, EXCLUDED_CHARS};
+use crate::error::BrokenPipeHandler;
+use crate::trust::{self, Trust};
+use crate::util::{self, ALIAS_DELIMITER};
+use anyhow::{ensure, Context, Result};
+use clap::{Parser, ValueEnum};
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(ValueEnum, Clone)]
+#[value(rename_all = "kebab-case")]
+pub enum ImportFormat {
+  /// `alias name='command'` lines, as printed by bash `alias` or zsh `alias -L`
+  BashAliases,
+  /// JSON file written by `abbr export --format json`
+  Json,
+  /// TOML file written by `abbr export --format toml`
+  Toml,
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Parser)]
+pub struct Import {
+  /// Format of the file to import
+  #[arg(long, value_enum)]
+  pub from: ImportFormat,
+  /// File to import, reads from stdin if omitted or `-`
+  pub file: Option<PathBuf>,
+  /// Show what would be imported without changing the database
+  #[arg(long)]
+  pub dry_run: bool,
+  /// Replace existing aliases that point to a different command
+  #[arg(long)]
+  pub force: bool,
+}
+
+/// What happened to a single entry of the imported file.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Eq)]
+enum Outcome {
+  Added { alias: String, origin: String },
+  Replaced { alias: String, origin: String, previous: String },
+  Unchanged { alias: String },
+  Conflict { alias: String, origin: String, existing: String },
+  Skipped { line: usize, reason: String },
+}
+
+impl Display for Outcome {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Outcome::Added { alias, origin } => {
+        write!(f, "added     {alias}: {origin}")
+      }
+      Outcome::Replaced { alias, origin, previous } => {
+        write!(f, "replaced  {alias}: {origin} (was: {previous})")
+      }
+      Outcome::Unchanged { alias } => write!(f, "unchanged {alias}"),
+      Outcome::Conflict { alias, origin, existing } => {
+        write!(f, "conflict  {alias}: {origin} (exists as: {existing})")
+      }
+      Outcome::Skipped { line, reason } => {
+        write!(f, "skipped   line {line}: {reason}")
+      }
+    }
+  }
+}
+
+impl Execute for Import {
+  fn execute(&self) -> Result<()> {
+    let contents = self.read_input()?;
+    let entries = match self.from {
+      ImportFormat::BashAliases => parse_bash_aliases(&contents),
+      ImportFormat::Json => {
+        export::from_json(&contents)?.into_iter().map(Ok).collect()
+      }
+      ImportFormat::Toml => {
+        export::from_toml(&contents)?.into_iter().map(Ok).collect()
+      }
+    };
+
+    let mut db = Database::open()?;
+    let outcomes = self.import(&mut db, entries);
+    let changed = outcomes.iter().any(|outcome| {
+      matches!(outcome, Outcome::Added { .. } | Outcome::Replaced { .. })
+    });
+    if changed && !self.dry_run {
+      db.save()?;
+    }
+
+    let handle = &mut io::stdout();
+    for outcome in outcomes.iter() {
+      writeln!(handle, "{outcome}").pipe_exit("stdout")?;
+    }
+    if outcomes
+      .iter()
+      .any(|outcome| matches!(outcome, Outcome::Conflict { .. }))
+    {
+      writeln!(handle, "use --force to replace conflicting aliases")
+        .pipe_exit("stdout")?;
+    }
+    if self.dry_run {
+      writeln!(handle, "dry run, database not modified").pipe_exit("stdout")?;
+    }
+
+    Ok(())
+  }
+}
+
+impl Import {
+  fn read_input(&self) -> Result<String> {
+    match self.file.as_deref() {
+      Some(path) if path.as_os_str() != "-" => {
+        let contents = fs::read_to_string(path).with_context(|| {
+          format!("failed to read file: {}", path.display())
+        })?;
+        // A dry run only shows the file, which is how it can be reviewed.
+        if !self.dry_run && trust::is_external(path) {
+          let allowed = Trust::open()?.check(path, contents.as_bytes())?;
+          ensure!(allowed, "{} is denied", path.display());
+        }
+        Ok(contents)
+      }
+      _ => {
+        let mut contents = String::new();
+        io::stdin()
+          .read_to_string(&mut contents)
+          .context("failed to read from stdin")?;
+        Ok(contents)
+      }
+    }
+  }
+
+  fn import(
+    &self,
+    db: &mut impl DbOperation,
+    entries: Vec<Result<Record<'static>, Outcome>>,
+  ) -> Vec<Outcome> {
+    let mut outcomes = Vec::with_capacity(entries.len());
+    for entry in entries {
+      let record = match entry {
+        Ok(record) => record,
+        Err(skipped) => {
+          outcomes.push(skipped);
+          continue;
+        }
+      };
+
+      let alias = record.alias.to_string();
+      let origin = record.origin.to_string();
+      let existing = db
+        .records()
+        .iter()
+        .find(|r| r.alias == record.alias)
+        .map(|r| (r.origin.to_string(), r.same_as(&record)));
+      let outcome = match existing {
+        None => Outcome::Added { alias, origin },
+        Some((existing, true)) if existing == origin => {
+          outcomes.push(Outcome::Unchanged { alias });
+          continue;
+        }
+        Some((previous, _)) if self.force => {
+          Outcome::Replaced { alias, origin, previous }
+        }
+        Some((existing, _)) => {
+          outcomes.push(Outcome::Conflict { alias, origin, existing });
+          continue;
+        }
+      };
+
+      db.add_record(record);
+      outcomes.push(outcome);
+    }
+
+    outcomes
+  }
+}
+
+/// Parses `alias name='command'` lines, ignoring anything that isn't an alias
+/// definition.
+fn parse_bash_aliases(contents: &str) -> Vec<Result<Record<'static>, Outcome>> {
+  let mut entries = Vec::new();
+  for (idx, line) in contents.lines().enumerate() {
+    let line_no = idx + 1;
+    let skipped =
+      |reason: String| Err(Outcome::Skipped { line: line_no, reason });
+
+    let Some(definitions) = line.trim().strip_prefix("alias ") else {
+      continue;
+    };
+    let words = match util::split_words(definitions) {
+      Ok(words) => words,
+      Err(e) => {
+        entries.push(skipped(e.to_string()));
+        continue;
+      }
+    };
+
+    for word in words {
+      // A trailing comment ends the definitions on this line.
+      if word.starts_with('#') {
+        break;
+      }
+      // Options such as zsh's `alias -g` change the meaning of the alias.
+      if word.starts_with('-') {
+        entries.push(skipped(format!("unsupported option `{word}`")));
+        break;
+      }
+      let entry = match word.split_once(ALIAS_DELIMITER) {
+        Some((alias, origin)) if alias.is_empty() || origin.is_empty() => {
+          skipped(format!("empty alias or command in `{word}`"))
+        }
+        Some((alias, origin))
+          if alias.contains(EXCLUDED_CHARS)
+            || origin.contains(EXCLUDED_CHARS) =>
+        {
+          skipped(format!("`{alias}` spans multiple lines"))
+        }
+        Some((alias, origin)) => Ok(Record {
+          origin: origin.to_owned().into(),
+          alias: alias.to_owned().into(),
+          mappings: Vec::new(),
+          defaults: Vec::new(),
+          combined_flags: false,
+        }),
+        None => skipped(format!("`{word}` is not a definition")),
+      };
+      entries.push(entry);
+    }
+  }
+
+  entries
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::command::import::{
+    parse_bash_aliases, Import, ImportFormat, Outcome,
+  };
+  use crate::database::{test::DummyDatabase, DbOperation};
+
+  const ALIASES: &str = r#"
+# Git
+alias gs='git status' # short status
+alias gl='git log --oneline' gd="git diff"
+export PATH="$HOME/bin:$PATH"
+alias it='echo it'\''s'
+alias e='echo'
+alias ca='cargo build'
+alias -g G='| grep'
+alias broken='unterminated
+  # alias old='git whatchanged'
+"#;
+
+  fn import(force: bool) -> (DummyDatabase, Vec<Outcome>) {
+    let mut db = DummyDatabase::open().unwrap();
+    let import = Import {
+      from: ImportFormat::BashAliases,
+      file: None,
+      dry_run: false,
+      force,
+    };
+    let outcomes = import.import(&mut db, parse_bash_aliases(ALIASES));
+    (db, outcomes)
+  }
+
+  #[test]
+  fn test_parse_bash_aliases() {
+    let (db, outcomes) = import(false);
+
+    assert_eq!(
+      outcomes,
+      vec![
+        Outcome::Added {
+          alias: "gs".to_string(),
+          origin: "git status".to_string()
+        },
+        Outcome::Added {
+          alias: "gl".to_string(),
+          origin: "git log --oneline".to_string()
+        },
+        Outcome::Added {
+          alias: "gd".to_string(),
+          origin: "git diff".to_string()
+        },
+        Outcome::Added {
+          alias: "it".to_string(),
+          origin: "echo it's".to_string()
+        },
+        Outcome::Unchanged { alias: "e".to_string() },
+        Outcome::Conflict {
+          alias: "ca".to_string(),
+          origin: "cargo build".to_string(),
+          existing: "cargo".to_string()
+        },
+        Outcome::Skipped {
+          line: 9,
+          reason: "unsupported option `-g`".to_string()
+        },
+        Outcome::Skipped {
+          line: 10,
+          reason: "unterminated single quote in `broken='unterminated`"
+            .to_string()
+        },
+      ]
+    );
+    let record = db.records().iter().find(|r| r.alias == "ca").unwrap();
+    assert_eq!(record.origin, "cargo");
+  }
+
+  #[test]
+  fn test_import_force() {
+    let (db, outcomes) = import(true);
+
+    assert!(outcomes.contains(&Outcome::Replaced {
+      alias: "ca".to_string(),
+      origin: "cargo build".to_string(),
+      previous: "cargo".to_string()
+    }));
+    let record = db.records().iter().find(|r| r.alias == "ca").unwrap();
+    assert_eq!(record.origin, "cargo build");
+  }
+}
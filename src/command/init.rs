@@ -1,6 +1,7 @@
 use crate::command::Execute;
+use crate::config::{Config, Key};
 use crate::error::BrokenPipeHandler;
-use crate::shell::{Bash, Opts, Zsh};
+use crate::shell::{Bash, Fish, Nushell, Opts, Powershell, Zsh};
 use anyhow::{Context, Result};
 use clap::{Parser, ValueEnum};
 use rinja::Template;
@@ -11,6 +12,9 @@ use std::io::{self, Write};
 #[value(rename_all = "kebab-case")]
 pub enum InitShell {
   Bash,
+  Fish,
+  Nushell,
+  Powershell,
   Zsh,
 }
 
@@ -20,16 +24,23 @@ pub struct Init {
   /// Current shell to initialize
   #[arg(value_enum)]
   pub shell: InitShell,
-  /// Set alias for this tool
-  #[arg(long, default_value = "ab")]
-  pub alias: String,
+  /// Set alias for this tool, defaults to the `alias` setting or `ab`
+  #[arg(long)]
+  pub alias: Option<String>,
 }
 
 impl Execute for Init {
   fn execute(&self) -> Result<()> {
-    let opts = Opts { cmd: Some(self.alias.as_str()) };
+    let alias = match &self.alias {
+      Some(alias) => alias.clone(),
+      None => Config::load()?.get(Key::Alias)?,
+    };
+    let opts = Opts { cmd: Some(alias.as_str()) };
     let source = match self.shell {
       InitShell::Bash => Bash(&opts).render(),
+      InitShell::Fish => Fish(&opts).render(),
+      InitShell::Nushell => Nushell(&opts).render(),
+      InitShell::Powershell => Powershell(&opts).render(),
       InitShell::Zsh => Zsh(&opts).render(),
     }
     .context("failed to render template")?;
@@ -0,0 +1,237 @@
+use crate::command::Execute;
+use crate::database::{Database, DbOperation, Record};
+use crate::error::BrokenPipeHandler;
+use crate::project::{self, Layer, Project};
+use crate::util::ALIAS_DELIMITER;
+use anyhow::Result;
+use clap::Parser;
+use std::io::{self, Write};
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Parser)]
+pub struct List {
+  /// Only show aliases starting with this prefix
+  pub prefix: Option<String>,
+  /// Only show aliases whose origin contains this text
+  #[arg(long)]
+  pub origin: Option<String>,
+  /// Show where each alias is defined, the project file or the global
+  /// database
+  #[arg(long)]
+  pub source: bool,
+  /// Print completion candidates: aliases, or the parameters of ALIAS
+  #[arg(long, hide = true, value_name = "ALIAS", num_args = 0..=1)]
+  pub complete: Option<Option<String>>,
+}
+
+impl Execute for List {
+  fn execute(&self) -> Result<()> {
+    let project = Project::find();
+    let db = Database::open_readonly()?;
+    let records = project::layer(project.as_ref(), db.records());
+    let lines = match &self.complete {
+      Some(alias) => self.build_candidates(&records, alias.as_deref()),
+      None => self.build_table(&records),
+    };
+    let handle = &mut io::stdout();
+    for line in lines {
+      writeln!(handle, "{line}").pipe_exit("stdout")?;
+    }
+
+    Ok(())
+  }
+}
+
+impl List {
+  fn filter(&self, record: &Record) -> bool {
+    self.prefix.as_deref().is_none_or(|prefix| record.alias.starts_with(prefix))
+      && self
+        .origin
+        .as_deref()
+        .is_none_or(|origin| record.origin.contains(origin))
+  }
+
+  /// Lists `<alias>\t<origin>` pairs, which shells such as fish show as a
+  /// completion with a description.
+  fn build_candidates(
+    &self,
+    records: &[(Layer, &Record)],
+    alias: Option<&str>,
+  ) -> Vec<String> {
+    let mut candidates = match alias {
+      Some(alias) => records
+        .iter()
+        .find(|(_, record)| record.alias == alias)
+        .map(|(_, record)| {
+          record
+            .mappings
+            .iter()
+            .map(|(alias, origin)| format!("{alias}\t{origin}"))
+            .collect()
+        })
+        .unwrap_or_default(),
+      None => records
+        .iter()
+        .filter(|(_, record)| self.filter(record))
+        .map(|(_, record)| format!("{}\t{}", record.alias, record.origin))
+        .collect::<Vec<_>>(),
+    };
+    candidates.sort_unstable();
+    candidates
+  }
+
+  fn build_table(&self, records: &[(Layer, &Record)]) -> Vec<String> {
+    let mut records = records
+      .iter()
+      .filter(|(_, record)| self.filter(record))
+      .collect::<Vec<_>>();
+    records.sort_unstable_by(|(_, a), (_, b)| a.alias.cmp(&b.alias));
+
+    let sources = records
+      .iter()
+      .map(
+        |(layer, _)| {
+          if self.source {
+            layer.to_string()
+          } else {
+            String::new()
+          }
+        },
+      )
+      .collect::<Vec<_>>();
+    let source_width =
+      sources.iter().map(String::len).max().unwrap_or_default();
+    let alias_width = records
+      .iter()
+      .map(|(_, record)| record.alias.len())
+      .max()
+      .unwrap_or_default();
+    let origin_width = records
+      .iter()
+      .map(|(_, record)| record.origin.len())
+      .max()
+      .unwrap_or_default();
+
+    records
+      .into_iter()
+      .zip(sources)
+      .map(|((_, record), source)| {
+        let mut mappings = record.mappings.iter().collect::<Vec<_>>();
+        mappings.sort_unstable();
+        // Print parameters in the same `<origin>=<alias>` format they are
+        // entered with.
+        let params = mappings
+          .into_iter()
+          .map(|(alias, origin)| format!("{origin}{ALIAS_DELIMITER}{alias}"))
+          .collect::<Vec<_>>()
+          .join(", ");
+        let line = format!(
+          "{:alias_width$}  {:origin_width$}  {params}",
+          record.alias, record.origin
+        );
+        if self.source {
+          format!("{source:source_width$}  {}", line.trim_end())
+        } else {
+          line.trim_end().to_owned()
+        }
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::command::list::List;
+  use crate::database::{test::DummyDatabase, DbOperation};
+  use crate::project::{self, test::write_project, Project, FILE_NAME};
+
+  #[test]
+  fn test_list_all() {
+    let db = DummyDatabase::open().unwrap();
+    let list =
+      List { prefix: None, origin: None, source: false, complete: None };
+
+    assert_eq!(
+      list.build_table(&project::layer(None, db.records())),
+      vec![
+        "ca   cargo       add=a",
+        "dk   docker      compose=cmp, image=i, ls=l, up -d=ud",
+        "e    echo",
+        "gtd  git tag -d",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_list_prefix() {
+    let db = DummyDatabase::open().unwrap();
+    let list = List {
+      prefix: Some("g".to_string()),
+      origin: None,
+      source: false,
+      complete: None,
+    };
+
+    assert_eq!(
+      list.build_table(&project::layer(None, db.records())),
+      vec!["gtd  git tag -d"]
+    );
+  }
+
+  #[test]
+  fn test_list_origin() {
+    let db = DummyDatabase::open().unwrap();
+    let list = List {
+      prefix: None,
+      origin: Some("ker".to_string()),
+      source: false,
+      complete: None,
+    };
+
+    assert_eq!(
+      list.build_table(&project::layer(None, db.records())),
+      vec!["dk  docker  compose=cmp, image=i, ls=l, up -d=ud"]
+    );
+  }
+
+  #[test]
+  fn test_list_candidates() {
+    let db = DummyDatabase::open().unwrap();
+    let list =
+      List { prefix: None, origin: None, source: false, complete: Some(None) };
+    let records = project::layer(None, db.records());
+
+    assert_eq!(
+      list.build_candidates(&records, None),
+      vec!["ca\tcargo", "dk\tdocker", "e\techo", "gtd\tgit tag -d"]
+    );
+    assert_eq!(
+      list.build_candidates(&records, Some("dk")),
+      vec!["cmp\tcompose", "i\timage", "l\tls", "ud\tup -d"]
+    );
+    assert!(list.build_candidates(&records, Some("missing")).is_empty());
+  }
+
+  #[test]
+  fn test_list_source() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(FILE_NAME);
+    let trust = write_project(
+      dir.path(),
+      "[[records]]\nalias = \"e\"\norigin = \"echo hi\"\n",
+    );
+    let project = Project::find_from(dir.path(), &trust).unwrap();
+    let db = DummyDatabase::open().unwrap();
+    let list = List {
+      prefix: Some("e".to_string()),
+      origin: None,
+      source: true,
+      complete: None,
+    };
+
+    assert_eq!(
+      list.build_table(&project::layer(project.as_ref(), db.records())),
+      vec![format!("{}  e  echo hi", path.display())]
+    );
+  }
+}
@@ -1,8 +1,11 @@
+use crate::command::edit::{self, Change};
 use crate::command::Execute;
 use crate::database::{Database, DbOperation, Record};
+use crate::error::BrokenPipeHandler;
 use crate::util;
 use anyhow::Result;
 use clap::Parser;
+use std::io::{self, Write};
 
 #[cfg_attr(debug_assertions, derive(Debug))]
 #[derive(Parser)]
@@ -13,6 +16,13 @@ pub struct New {
   pub command: String,
   /// Command parameter(s), format: `<origin>=<alias>`
   pub params: Vec<String>,
+  /// Default argument(s), appended when fewer arguments are given
+  #[arg(long = "default", value_name = "ARG")]
+  pub defaults: Vec<String>,
+  /// Expand combined single-letter parameters, such as `-aq`, letter by
+  /// letter
+  #[arg(long)]
+  pub combined_flags: bool,
 }
 
 impl Execute for New {
@@ -20,26 +30,32 @@ impl Execute for New {
     let mut db = Database::open()?;
 
     let (origin, alias) = util::alias_to_pair(self.command.as_ref())?;
-    let mappings = self
-      .params
+    let params = edit::parse_params(&self.params)?;
+    let mappings = params
       .iter()
-      .map(|pair| {
-        util::alias_to_pair(pair.as_ref()).map(|(origin, alias)| {
-          (origin.to_owned().into(), alias.to_owned().into())
-        })
+      .map(|(origin, alias)| {
+        (alias.to_string().into(), origin.to_string().into())
       })
-      .flatten()
-      .collect::<Vec<_>>();
+      .collect();
 
     let record = Record {
       origin: origin.to_owned().into(),
       alias: alias.to_owned().into(),
       mappings,
+      defaults: self.defaults.iter().map(|d| d.clone().into()).collect(),
+      combined_flags: self.combined_flags,
     };
 
     db.add_record(record);
     db.save()?;
 
+    let handle = &mut io::stdout();
+    for (origin, param) in params {
+      let change =
+        Change::Added { param: param.to_owned(), origin: origin.to_owned() };
+      writeln!(handle, "{change}").pipe_exit("stdout")?;
+    }
+
     Ok(())
   }
 }
@@ -0,0 +1,119 @@
+use crate::command::config::warn_overridden;
+use crate::command::Execute;
+use crate::config::{self, Config, Key};
+use crate::error::BrokenPipeHandler;
+use anyhow::{bail, ensure, Context, Result};
+use clap::{Parser, Subcommand};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Subcommand)]
+#[command(disable_help_subcommand = true)]
+pub enum Action {
+  /// List profiles, marking the active one
+  List,
+  /// Create a new, empty profile
+  Create { name: String },
+  /// Switch to a profile, `_ABBR_PROFILE` pins one for a single shell
+  Use { name: String },
+  /// Delete a profile along with its aliases
+  Delete { name: String },
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Parser)]
+pub struct Profile {
+  #[clap(subcommand)]
+  /// Action to perform
+  pub action: Action,
+}
+
+impl Execute for Profile {
+  fn execute(&self) -> Result<()> {
+    let config = Config::load()?;
+    let profiles_dir = config::profiles_dir(&config.data_dir()?);
+    let handle = &mut io::stdout();
+
+    match &self.action {
+      Action::List => {
+        let active = config.get(Key::Profile)?;
+        for name in list(&profiles_dir)? {
+          let marker = if name == active { '*' } else { ' ' };
+          writeln!(handle, "{marker} {name}").pipe_exit("stdout")?;
+        }
+      }
+      Action::Create { name } => {
+        validate(name)?;
+        let dir = profiles_dir.join(name);
+        ensure!(!dir.exists(), "profile `{name}` already exists");
+        fs::create_dir_all(&dir).with_context(|| {
+          format!("unable to create profile directory: {}", dir.display())
+        })?;
+        writeln!(handle, "created profile `{name}`").pipe_exit("stdout")?;
+      }
+      Action::Use { name } => {
+        config::validate_profile(name)?;
+        ensure!(
+          name == Config::DEFAULT_PROFILE || profiles_dir.join(name).is_dir(),
+          "profile `{name}` does not exist, create it with `abbr profile create {name}`"
+        );
+        let path = config::config_path()?;
+        let mut file = Config::read(&path)?;
+        file.set(Key::Profile, name)?;
+        file.write(&path)?;
+        writeln!(handle, "using profile `{name}`").pipe_exit("stdout")?;
+        warn_overridden(Key::Profile);
+      }
+      Action::Delete { name } => {
+        validate(name)?;
+        let dir = profiles_dir.join(name);
+        ensure!(dir.is_dir(), "profile `{name}` does not exist");
+        let file = Config::read(&config::config_path()?)?;
+        for profile in [&config.profile, &file.profile] {
+          if profile.as_ref() == Some(name) {
+            bail!("profile `{name}` is in use, switch to another one first");
+          }
+        }
+        fs::remove_dir_all(&dir).with_context(|| {
+          format!("failed to delete profile directory: {}", dir.display())
+        })?;
+        writeln!(handle, "deleted profile `{name}`").pipe_exit("stdout")?;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// Validates the name of a profile other than the default one.
+fn validate(name: &str) -> Result<()> {
+  config::validate_profile(name)?;
+  ensure!(
+    name != Config::DEFAULT_PROFILE,
+    "the {} profile can't be created or deleted",
+    Config::DEFAULT_PROFILE
+  );
+  Ok(())
+}
+
+/// Names of all profiles, the default one first.
+fn list(profiles_dir: &Path) -> Result<Vec<String>> {
+  let mut names = match fs::read_dir(profiles_dir) {
+    Ok(entries) => entries
+      .filter_map(|entry| entry.ok())
+      .filter(|entry| entry.path().is_dir())
+      .filter_map(|entry| entry.file_name().into_string().ok())
+      .collect::<Vec<_>>(),
+    Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+    Err(e) => {
+      return Err(e).with_context(|| {
+        format!("failed to list profiles: {}", profiles_dir.display())
+      });
+    }
+  };
+  names.sort_unstable();
+  names.insert(0, Config::DEFAULT_PROFILE.to_owned());
+  Ok(names)
+}
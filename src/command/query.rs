@@ -1,133 +1,868 @@
+use crate::command::init::InitShell;
 use crate::command::Execute;
-use crate::database::{Database, DbOperation};
-use crate::util;
-use anyhow::{Context, Result};
+use crate::config::{Config, Quoting};
+use crate::database::{MappedDatabase, Record, Str};
+use crate::error::BrokenPipeHandler;
+use crate::project::Project;
+use crate::shell;
+use crate::util::{self, Amount};
+use anyhow::{bail, ensure, Context, Result};
 use clap::Parser;
 use hashbrown::HashMap;
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::fmt::{self, Display, Formatter};
 use std::io::{self, Write};
+use std::iter;
+use std::mem;
 
 #[cfg_attr(debug_assertions, derive(Debug))]
 #[derive(Parser)]
 #[command(disable_help_flag = true, disable_help_subcommand = true)]
 pub struct Query {
+  /// Shell to quote the expanded command for
+  #[arg(long, value_enum, default_value = "bash")]
+  pub shell: InitShell,
+  /// Print how each argument is interpreted instead of the expanded command
+  #[arg(long)]
+  pub explain: bool,
   #[arg(required = true)]
   pub alias: String,
+  #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
   pub params: Vec<String>,
 }
 
+/// A single word of an expanded command.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, PartialEq, Eq)]
+enum Token<'s> {
+  /// Stored by the user, emitted as-is so it can hold shell syntax.
+  Origin(&'s str),
+  /// Typed by the user, quoted so the shell sees it verbatim.
+  Param(&'s str),
+  /// Typed by the user as `param\/N`, with the escape removed.
+  Unescaped(String),
+  /// Stored command with user arguments filled into its placeholders.
+  Template(Vec<Piece<'s>>),
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, PartialEq, Eq)]
+enum Piece<'s> {
+  Text(&'s str),
+  Arg(Token<'s>),
+}
+
+/// Starts an origin that expands another alias, as in `@dk cmp`.
+const REFERENCE: char = '@';
+/// How many aliases an origin may go through before reaching a command.
+const MAX_DEPTH: usize = 8;
+
+/// An argument and how it was interpreted, printed by `--explain`.
+type Step<'s> = (&'s str, Meaning<'s>);
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Eq)]
+enum Meaning<'s> {
+  /// Replaced by a mapping, scoped to the word it follows unless empty.
+  Mapped {
+    origin: &'s str,
+    scope: &'s str,
+  },
+  Passed,
+  /// Starts a `param/N` group, which passes the next N arguments through.
+  Group {
+    origin: Option<&'s str>,
+    amount: Amount,
+  },
+  /// Passed through as `param/N`, without starting a group.
+  Escaped(String),
+  Consumed {
+    group: &'s str,
+  },
+  /// Split into single-letter parameters, such as `-aq`, each one mapped.
+  Combined(Vec<&'s str>),
+  /// Filled in from the record's defaults for a missing argument.
+  Default {
+    position: usize,
+  },
+  /// Moved into a `{N}` or `{name}` placeholder, after being interpreted as
+  /// `meaning`.
+  Placeholder {
+    placeholder: String,
+    meaning: Box<Meaning<'s>>,
+  },
+}
+
+impl Display for Meaning<'_> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Meaning::Mapped { origin, scope: "" } => {
+        write!(f, "mapped to `{origin}`")
+      }
+      Meaning::Mapped { origin, scope } => {
+        write!(f, "mapped to `{origin}` after `{scope}`")
+      }
+      Meaning::Passed => write!(f, "passed through"),
+      Meaning::Group { origin, amount } => {
+        match origin {
+          Some(origin) => write!(f, "mapped to `{origin}`")?,
+          None => write!(f, "passed through")?,
+        }
+        match amount {
+          Amount::Count(1) => write!(f, ", takes the next argument as it is"),
+          Amount::Count(count) => {
+            write!(f, ", takes the next {count} arguments as they are")
+          }
+          Amount::Rest => {
+            write!(f, ", takes the remaining arguments as they are")
+          }
+        }
+      }
+      Meaning::Consumed { group } => write!(f, "consumed by `{group}`"),
+      Meaning::Escaped(literal) => write!(f, "passed through as `{literal}`"),
+      Meaning::Combined(origins) => {
+        let origins = origins
+          .iter()
+          .map(|origin| format!("`{origin}`"))
+          .collect::<Vec<_>>();
+        write!(f, "split and mapped to {}", origins.join(", "))
+      }
+      Meaning::Default { position } => {
+        write!(f, "default for argument {position}")
+      }
+      Meaning::Placeholder { placeholder, meaning } => match **meaning {
+        Meaning::Passed => write!(f, "fills {placeholder}"),
+        ref meaning => write!(f, "{meaning}, fills {placeholder}"),
+      },
+    }
+  }
+}
+
+/// Mappings of a record, nested under the words they are scoped to, such as
+/// `compose.l` for `l` following `compose`.
+#[derive(Default)]
+struct Scope<'s> {
+  /// Mappings by the first word of their key, which may span several words.
+  mappings: HashMap<Cow<'s, str>, Vec<Mapping<'s>>>,
+  children: HashMap<Cow<'s, str>, Scope<'s>>,
+}
+
+struct Mapping<'s> {
+  key: &'s str,
+  /// Words of `key`, with escaped dots unescaped.
+  words: Vec<Cow<'s, str>>,
+  origin: &'s str,
+}
+
+impl<'s> Scope<'s> {
+  fn build(mappings: &'s [(Str, Str)]) -> Self {
+    let mut root = Self::default();
+    for (alias, origin) in mappings {
+      let (scope, key) = util::param_to_scope(alias);
+      let words =
+        key.split_whitespace().map(util::unescape_scope).collect::<Vec<_>>();
+      let Some(first) = words.first().cloned() else {
+        continue;
+      };
+      let node = scope
+        .into_iter()
+        .flat_map(util::scope_words)
+        .fold(&mut root, |node, word| node.children.entry(word).or_default());
+      node.mappings.entry(first).or_default().push(Mapping {
+        key,
+        words,
+        origin,
+      });
+    }
+    root
+  }
+
+  /// Finds the mapping whose key matches the most of the leading `params`,
+  /// preferring the innermost scope when several match as many, along with
+  /// the word that scope follows.
+  fn get<'p>(
+    path: &'p [(&'s str, &'p Self)],
+    params: &[&str],
+  ) -> Option<(&'s str, &'p Mapping<'s>)> {
+    let first = *params.first()?;
+    path
+      .iter()
+      .rev()
+      .flat_map(|(scope, node)| {
+        let mappings = node.mappings.get(first).map(Vec::as_slice);
+        mappings.unwrap_or_default().iter().map(move |m| (*scope, m))
+      })
+      .filter(|(_, mapping)| {
+        params.get(..mapping.words.len()).is_some_and(|params| {
+          mapping.words.iter().map(AsRef::as_ref).eq(params.iter().copied())
+        })
+      })
+      .min_by_key(|(_, mapping)| Reverse(mapping.words.len()))
+  }
+
+  /// Maps each letter of a word of single-letter flags, such as `-aq` to `-a`
+  /// and `-q`, unless one of them isn't mapped. Words without a dash are
+  /// never split, since they are more likely a value than flags.
+  fn get_combined(
+    path: &[(&'s str, &Self)],
+    word: &str,
+  ) -> Option<Vec<&'s str>> {
+    let letters = word.strip_prefix('-')?;
+    if letters.starts_with('-') || letters.chars().count() < 2 {
+      return None;
+    }
+
+    letters
+      .chars()
+      .map(|letter| {
+        // A letter may be mapped with or without its dash.
+        let key = format!("-{letter}");
+        Self::get(path, &[&key])
+          .or_else(|| Self::get(path, &[&key[1..]]))
+          .map(|(_, mapping)| mapping.origin)
+      })
+      .collect()
+  }
+
+  /// Enters the scope of `word` if it has one, leaving the scopes it isn't
+  /// nested in.
+  fn enter<'t>(path: &mut Vec<(&'s str, &'t Self)>, word: &'s str) {
+    let Some(depth) =
+      path.iter().rposition(|(_, node)| node.children.contains_key(word))
+    else {
+      return;
+    };
+    let node = path[depth].1;
+    path.truncate(depth + 1);
+    path.push((word, &node.children[word]));
+  }
+}
+
+/// Part of a stored command, split around `{N}` and `{name}` placeholders.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Eq)]
+pub enum Part<'s> {
+  Text(&'s str),
+  /// Filled by the N-th argument, counting from 1.
+  Index(usize),
+  /// Filled by a `name=value` argument.
+  Name(&'s str),
+}
+
 impl Execute for Query {
   fn execute(&self) -> Result<()> {
-    let db = Database::open()?;
-    let order = self.build_order(&db)?;
+    let config = Config::load()?;
+    let project = Project::find();
+    let db = MappedDatabase::open(&config)?;
+    let global;
+    let record = match project.as_ref().and_then(|p| p.get(&self.alias)) {
+      Some(record) => record,
+      None => {
+        global = match db.get(&self.alias)? {
+          Some(record) => record,
+          None if config.fuzzy => self.closest(project.as_ref(), &db)?,
+          None => bail!("alias `{}` not found", self.alias),
+        };
+        &global
+      }
+    };
+    let lookup = |alias: &str| match project.as_ref().and_then(|p| p.get(alias))
+    {
+      Some(record) => Ok(Some(record.clone())),
+      None => db.get(alias),
+    };
+    let resolved = resolve(record, lookup)?;
+    let (order, steps) = self.expand(&resolved)?;
+    let command = self.render(&order, config.quoting)?;
     let handle = &mut io::stdout();
-    write!(handle, "{}", order.join(" "))?;
+    if self.explain {
+      for line in explain(record, &resolved, &steps) {
+        writeln!(handle, "{line}").pipe_exit("stdout")?;
+      }
+      writeln!(handle, "expands to: {command}").pipe_exit("stdout")?;
+    } else {
+      write!(handle, "{command}")?;
+    }
 
     Ok(())
   }
 }
 
 impl Query {
-  fn build_order<'s>(
-    &'s self,
-    db: &'s impl DbOperation,
-  ) -> Result<Vec<&'s str>> {
-    let record = db
-      .records()
-      .iter()
-      .find(|record| record.alias == self.alias)
-      .context(format!("alias `{}` not found", self.alias))?;
+  /// Resolves a mistyped alias to the closest existing one.
+  fn closest<'d>(
+    &self,
+    project: Option<&Project>,
+    db: &'d MappedDatabase,
+  ) -> Result<Record<'d>> {
+    let mut records = db.records()?;
+    // Project records come last, so that they win over the ones they shadow.
+    records.extend(project.iter().flat_map(|p| p.records.iter().cloned()));
 
-    let mut order = Vec::with_capacity(self.params.len() + 1);
-    order.push(record.origin.as_ref());
+    let aliases = records.iter().map(|record| record.alias.as_ref());
+    // The expansion is evaluated, so it must not be a guess between equally
+    // close aliases.
+    let alias = match util::closest(&self.alias, aliases)[..] {
+      [alias] => alias,
+      ref closest => {
+        bail!("alias `{}` not found{}", self.alias, util::did_you_mean(closest))
+      }
+    };
+    let idx = records.iter().rposition(|r| r.alias == alias).unwrap();
+    let record = records.swap_remove(idx);
+    _ = writeln!(
+      io::stderr(),
+      "abbr: expanding `{}` for `{}`",
+      record.alias,
+      self.alias
+    );
+    Ok(record)
+  }
 
-    let mut mappings = HashMap::with_capacity(record.mappings.len());
-    for (alias, origin) in record.mappings.iter() {
-      mappings.insert(alias.as_ref(), origin.as_ref());
+  fn render(&self, order: &[Token], quoting: Quoting) -> Result<String> {
+    let quote: fn(&str) -> Cow<'_, str> = match (&self.shell, quoting) {
+      (InitShell::Nushell, _) => return self.render_argv(order),
+      (_, Quoting::Raw) => |word| Cow::Borrowed(word),
+      (InitShell::Bash | InitShell::Zsh, _) => shell::quote_posix,
+      (InitShell::Fish, _) => shell::quote_fish,
+      (InitShell::Powershell, _) => shell::quote_powershell,
+    };
+
+    Ok(
+      order
+        .iter()
+        .map(|token| Self::render_token(token, quote))
+        .collect::<Vec<_>>()
+        .join(" "),
+    )
+  }
+
+  fn render_token<'s>(
+    token: &Token<'s>,
+    quote: fn(&str) -> Cow<'_, str>,
+  ) -> Cow<'s, str> {
+    match *token {
+      Token::Origin(origin) => Cow::Borrowed(origin),
+      Token::Param(param) => quote(param),
+      Token::Unescaped(ref param) => Cow::Owned(quote(param).into_owned()),
+      Token::Template(ref pieces) => pieces
+        .iter()
+        .map(|piece| match piece {
+          Piece::Text(text) => Cow::Borrowed(*text),
+          Piece::Arg(token) => Self::render_token(token, quote),
+        })
+        .collect::<String>()
+        .into(),
     }
+  }
+
+  /// Renders the command as a JSON list of arguments, for shells that run it
+  /// directly instead of evaluating a string.
+  fn render_argv(&self, order: &[Token]) -> Result<String> {
+    let mut argv = Vec::with_capacity(order.len());
+    for token in order {
+      match token {
+        Token::Origin(origin) => argv.extend(util::split_words(origin)?),
+        Token::Param(param) => argv.push(param.to_string()),
+        Token::Unescaped(param) => argv.push(param.clone()),
+        Token::Template(_) => {
+          // Quote the arguments so splitting keeps each of them whole.
+          let quote = |param: &str| {
+            Cow::Owned(format!("'{}'", param.replace('\'', r"'\''")))
+          };
+          let command = Self::render_token(token, quote);
+          argv.extend(util::split_words(&command)?)
+        }
+      }
+    }
+
+    serde_json::to_string(&argv).context("failed to serialize arguments")
+  }
+
+  /// Builds the words of the expanded command, along with how each argument
+  /// was interpreted.
+  fn expand<'s>(
+    &'s self,
+    record: &'s Record,
+  ) -> Result<(Vec<Token<'s>>, Vec<Step<'s>>)> {
+    // Each word along with the step that explains it.
+    let mut order = Vec::with_capacity(self.params.len());
+    let mut steps = Vec::with_capacity(self.params.len());
+
+    let root = Scope::build(&record.mappings);
+    let mut path = vec![("", &root)];
+    let params = self.params.iter().map(String::as_str).collect::<Vec<_>>();
 
     let mut idx = 0;
-    while idx < self.params.len() {
-      let param = &self.params[idx];
-      match mappings.get(param.as_str()) {
-        Some(origin) => order.push(*origin),
-        None => match util::param_to_pair(param) {
+    while idx < params.len() {
+      let group = params[idx];
+      let word = match Scope::get(&path, &params[idx..]) {
+        Some((scope, mapping)) => {
+          let origin = mapping.origin;
+          order.push((Token::Origin(origin), steps.len()));
+          steps.push((mapping.key, Meaning::Mapped { origin, scope }));
+          idx += mapping.words.len() - 1;
+          origin
+        }
+        None => match util::param_to_pair(group) {
           Some((param, amount)) => {
-            match mappings.get(param) {
-              Some(origin) => order.push(*origin),
-              None => order.push(param),
+            let origin =
+              Scope::get(&path, &[param]).map(|(_, mapping)| mapping.origin);
+            let token = match origin {
+              Some(origin) => Token::Origin(origin),
+              None => Token::Param(param),
+            };
+            order.push((token, steps.len()));
+            let rest = &params[idx + 1..];
+            let count = match amount {
+              Amount::Count(count) => count,
+              Amount::Rest => rest.len(),
+            };
+            ensure!(
+              count <= rest.len(),
+              "`{group}` takes {count} {}, got {}",
+              if count == 1 { "argument" } else { "arguments" },
+              rest.len()
+            );
+            steps.push((group, Meaning::Group { origin, amount }));
+            for param in &rest[..count] {
+              order.push((Token::Param(param), steps.len()));
+              steps.push((param, Meaning::Consumed { group }));
             }
-            for i in 1..=amount {
-              order.push(&self.params[idx + i]);
+            idx += count;
+            origin.unwrap_or(param)
+          }
+          None => {
+            let combined = record
+              .combined_flags
+              .then(|| Scope::get_combined(&path, group))
+              .flatten();
+            match (combined, util::unescape_param(group)) {
+              (Some(origins), _) => {
+                let source = steps.len();
+                order.extend(
+                  origins.iter().map(|origin| (Token::Origin(origin), source)),
+                );
+                steps.push((group, Meaning::Combined(origins)));
+              }
+              (None, Some(literal)) => {
+                order.push((Token::Unescaped(literal.clone()), steps.len()));
+                steps.push((group, Meaning::Escaped(literal)));
+              }
+              (None, None) => {
+                order.push((Token::Param(group), steps.len()));
+                steps.push((group, Meaning::Passed));
+              }
             }
-            idx += amount;
+            group
           }
-          None => order.push(param),
         },
+      };
+      Scope::enter(&mut path, word);
+      idx += 1;
+    }
+
+    let template = parse_template(&record.origin);
+    let (mut order, named) = if let [Part::Text(origin)] = template.as_slice() {
+      let args = order.into_iter().map(|(token, _)| token);
+      (iter::once(Token::Origin(origin)).chain(args).collect(), 0)
+    } else {
+      self.fill_template(template, order, &mut steps)?
+    };
+
+    // Fill in defaults for the arguments that weren't typed, after the
+    // placeholders so that a default never fills one of them.
+    let given = self.params.len() - named;
+    if let Some(defaults) = record.defaults.get(given..) {
+      for (i, default) in defaults.iter().enumerate() {
+        order.push(Token::Origin(default));
+        steps.push((default, Meaning::Default { position: given + i + 1 }));
+      }
+    }
+    Ok((order, steps))
+  }
+
+  /// Moves arguments into the placeholders of a stored command, appending
+  /// the ones left over, and marks the steps of the arguments it moves. Also
+  /// returns how many arguments were taken by name.
+  fn fill_template<'s>(
+    &self,
+    template: Vec<Part<'s>>,
+    args: Vec<(Token<'s>, usize)>,
+    steps: &mut [Step<'s>],
+  ) -> Result<(Vec<Token<'s>>, usize)> {
+    let mut fills = |source: usize, placeholder: String| {
+      let (_, meaning) = &mut steps[source];
+      let previous = mem::replace(meaning, Meaning::Passed);
+      *meaning =
+        Meaning::Placeholder { placeholder, meaning: Box::new(previous) };
+    };
+
+    let names = template
+      .iter()
+      .filter_map(|part| match part {
+        Part::Name(name) => Some(*name),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+
+    let mut named = HashMap::with_capacity(names.len());
+    let mut positional = Vec::with_capacity(args.len());
+    for (token, source) in args {
+      if let Token::Param(param) = token {
+        if let Some((name, value)) = param.split_once(util::ALIAS_DELIMITER) {
+          if names.contains(&name) && !named.contains_key(name) {
+            named.insert(name, Token::Param(value));
+            fills(source, format!("{{{name}}}"));
+            continue;
+          }
+        }
       }
+      positional.push((token, source));
+    }
+
+    let mut used = vec![false; positional.len()];
+    let mut pieces = Vec::with_capacity(template.len());
+    for part in template {
+      let token = match part {
+        Part::Text(text) => {
+          pieces.push(Piece::Text(text));
+          continue;
+        }
+        Part::Index(idx) => {
+          let (token, source) = positional.get(idx - 1).with_context(|| {
+            format!("alias `{}` requires argument {{{idx}}}", self.alias)
+          })?;
+          if !mem::replace(&mut used[idx - 1], true) {
+            fills(*source, format!("{{{idx}}}"));
+          }
+          token
+        }
+        Part::Name(name) => named.get(name).with_context(|| {
+          format!(
+            "alias `{}` requires `{name}{}<value>` for {{{name}}}",
+            self.alias,
+            util::ALIAS_DELIMITER
+          )
+        })?,
+      };
+      pieces.push(Piece::Arg(token.clone()));
+    }
+
+    let mut order = Vec::with_capacity(positional.len() + 1);
+    order.push(Token::Template(pieces));
+    order.extend(
+      positional
+        .into_iter()
+        .zip(used)
+        .filter(|(_, used)| !used)
+        .map(|((token, _), _)| token),
+    );
+    Ok((order, named.len()))
+  }
+}
+
+/// Replaces a reference to another alias at the start of an origin, as in
+/// `@dk cmp`, by the expansion of the words that follow it, recursively.
+/// The record inherits the mappings of the aliases it references, overriding
+/// them with its own.
+pub fn resolve<'r, 'l>(
+  record: &'r Record<'r>,
+  lookup: impl Fn(&str) -> Result<Option<Record<'l>>>,
+) -> Result<Cow<'r, Record<'r>>> {
+  if !record.origin.starts_with(REFERENCE) {
+    return Ok(Cow::Borrowed(record));
+  }
+
+  let mut path = vec![record.alias.to_string()];
+  // Words following each reference, outermost first.
+  let mut args = Vec::new();
+  let mut origin = record.origin.to_string();
+  let mut mappings = Vec::new();
+  while let Some(reference) = origin.strip_prefix(REFERENCE) {
+    let (alias, rest) = reference.split_once(' ').unwrap_or((reference, ""));
+    if path.iter().any(|visited| visited == alias) {
+      bail!(
+        "alias `{}` references itself: {} -> {alias}",
+        path[0],
+        path.join(" -> ")
+      );
+    }
+    ensure!(
+      path.len() <= MAX_DEPTH,
+      "alias `{}` references more than {MAX_DEPTH} aliases in a row: {}",
+      path[0],
+      path.join(" -> ")
+    );
+    let Some(target) = lookup(alias)? else {
+      bail!(
+        "alias `{}` references `{alias}`, which doesn't exist",
+        path.last().unwrap()
+      );
+    };
+
+    args.push(rest.to_owned());
+    mappings.push(
+      target
+        .mappings
+        .iter()
+        .map(|(a, o)| (a.to_string(), o.to_string()))
+        .collect::<Vec<_>>(),
+    );
+    path.push(alias.to_owned());
+    origin = target.origin.into_owned();
+  }
+
+  // Expand from the innermost reference outwards, so that each one sees the
+  // mappings of everything it references, walking scopes and keys spanning
+  // several words like `Query::expand`.
+  let mut inherited = Vec::new();
+  for (rest, mappings) in args.into_iter().zip(mappings).rev() {
+    inherit(&mut inherited, mappings);
+    let root = Scope::build(&inherited);
+    let mut path = vec![("", &root)];
+    let words = rest.split_whitespace().collect::<Vec<_>>();
+    let mut idx = 0;
+    while idx < words.len() {
+      let word = match Scope::get(&path, &words[idx..]) {
+        Some((_, mapping)) => {
+          idx += mapping.words.len();
+          mapping.origin
+        }
+        None => {
+          idx += 1;
+          words[idx - 1]
+        }
+      };
+      origin.push(' ');
+      origin.push_str(word);
+      Scope::enter(&mut path, word);
+    }
+  }
+  inherit(
+    &mut inherited,
+    record.mappings.iter().map(|(a, o)| (a.to_string(), o.to_string())),
+  );
+
+  Ok(Cow::Owned(Record {
+    origin: origin.into(),
+    alias: record.alias.clone(),
+    mappings: inherited,
+    defaults: record.defaults.clone(),
+    combined_flags: record.combined_flags,
+  }))
+}
+
+/// Adds `mappings` to the ones `inherited` so far, replacing those with the
+/// same alias in place, so that the order stays the same between runs.
+fn inherit(
+  inherited: &mut Vec<(Str<'static>, Str<'static>)>,
+  mappings: impl IntoIterator<Item = (String, String)>,
+) {
+  for (alias, origin) in mappings {
+    match inherited.iter_mut().find(|(existing, _)| *existing == alias) {
+      Some(entry) => entry.1 = origin.into(),
+      None => inherited.push((alias.into(), origin.into())),
+    }
+  }
+}
+
+/// Lines describing how the arguments of an expansion were interpreted.
+fn explain(record: &Record, resolved: &Record, steps: &[Step]) -> Vec<String> {
+  let width = steps.iter().map(|(param, _)| param.len()).max().unwrap_or(0);
+  let mut lines = Vec::with_capacity(steps.len() + 1);
+  if record.origin == resolved.origin {
+    lines.push(format!("{}: {}", record.alias, record.origin));
+  } else {
+    lines.push(format!(
+      "{}: {} ({})",
+      record.alias, record.origin, resolved.origin
+    ));
+  }
+  lines.extend(
+    steps.iter().map(|(param, step)| format!("  {param:width$}  {step}")),
+  );
+  lines
+}
+
+/// Splits a stored command around its placeholders. Braces that don't form a
+/// placeholder, such as `{}` or `${VAR}`, are kept as text, and `{{` and `}}`
+/// stand for a literal brace, as in `git show HEAD@{{1}}`.
+pub fn parse_template(origin: &str) -> Vec<Part<'_>> {
+  let mut parts = Vec::new();
+  let mut start = 0;
+  let mut idx = 0;
+
+  while let Some(offset) = origin[idx..].find(['{', '}']) {
+    let open = idx + offset;
+    let brace = origin.as_bytes()[open];
+    idx = open + 1;
+    if origin.as_bytes().get(idx) == Some(&brace) {
+      // Keep the first brace of the pair, drop the second.
+      parts.push(Part::Text(&origin[start..idx]));
       idx += 1;
+      start = idx;
+      continue;
     }
+    if brace == b'}' || origin[..open].ends_with('$') {
+      continue;
+    }
+    let Some(close) = origin[idx..].find('}').map(|i| i + idx) else {
+      break;
+    };
 
-    Ok(order)
+    let inner = &origin[idx..close];
+    let part = if !inner.is_empty() && inner.bytes().all(|b| b.is_ascii_digit())
+    {
+      match inner.parse::<usize>() {
+        Ok(idx) if idx > 0 => Part::Index(idx),
+        _ => continue,
+      }
+    } else if inner.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+      && inner
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+    {
+      Part::Name(inner)
+    } else {
+      continue;
+    };
+
+    if start < open {
+      parts.push(Part::Text(&origin[start..open]));
+    }
+    parts.push(part);
+    start = close + 1;
+    idx = start;
   }
+  if start < origin.len() || parts.is_empty() {
+    parts.push(Part::Text(&origin[start..]));
+  }
+
+  parts
 }
 
 #[cfg(test)]
 mod tests {
-  use crate::command::query::Query;
-  use crate::database::{test::DummyDatabase, DbOperation};
+  use crate::command::init::InitShell;
+  use crate::command::query::{explain, parse_template, resolve, Part, Query};
+  use crate::config::Quoting;
+  use crate::database::{test::DummyDatabase, DbOperation, Record};
+  use anyhow::Result;
+  use std::process::Command;
+
+  fn record<'d>(db: &'d DummyDatabase, query: &Query) -> &'d Record<'d> {
+    db.records().iter().find(|r| r.alias == query.alias).unwrap()
+  }
+
+  /// Expands `alias` with `params` like `abbr query` does, references to
+  /// other aliases included.
+  fn expand(
+    db: &DummyDatabase,
+    alias: &str,
+    params: &[&str],
+  ) -> Result<String> {
+    let query = Query {
+      shell: InitShell::Bash,
+      explain: false,
+      alias: alias.to_string(),
+      params: params.iter().map(|p| p.to_string()).collect(),
+    };
+    let lookup =
+      |alias: &str| Ok(db.records().iter().find(|r| r.alias == alias).cloned());
+    let resolved = resolve(record(db, &query), lookup)?;
+    let (order, _) = query.expand(&resolved)?;
+    query.render(&order, Quoting::Escape)
+  }
+
+  fn eval_bash(script: &str) -> Vec<String> {
+    // Replace `echo` so each argument is printed NUL-terminated.
+    let output = Command::new("bash")
+      .arg("-c")
+      .arg(format!("echo() {{ printf '%s\\0' \"$@\"; }}; {script}"))
+      .output()
+      .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    stdout.split_terminator('\0').map(str::to_owned).collect()
+  }
 
   #[test]
   fn test_echo() {
     let db = DummyDatabase::open().unwrap();
     let query = Query {
+      explain: false,
+      shell: InitShell::Bash,
       alias: "e".to_string(),
       params: vec!["hello".to_string(), "world".to_string()],
     };
 
-    let order = query.build_order(&db).unwrap();
-    assert_eq!(order.join(" "), String::from("echo hello world"));
+    let (order, _) = query.expand(record(&db, &query)).unwrap();
+    assert_eq!(
+      query.render(&order, Quoting::Escape).unwrap(),
+      String::from("echo hello world")
+    );
   }
 
   #[test]
   fn test_git_tag() {
     let db = DummyDatabase::open().unwrap();
     let query = Query {
+      explain: false,
+      shell: InitShell::Bash,
       alias: "gtd".to_string(),
       params: vec!["tag1,tag2,tag3".to_string()],
     };
 
-    let order = query.build_order(&db).unwrap();
-    assert_eq!(order.join(" "), String::from("git tag -d tag1,tag2,tag3"));
+    let (order, _) = query.expand(record(&db, &query)).unwrap();
+    assert_eq!(
+      query.render(&order, Quoting::Escape).unwrap(),
+      String::from("git tag -d tag1,tag2,tag3")
+    );
   }
 
   #[test]
   fn test_docker_compose_up_d() {
     let db = DummyDatabase::open().unwrap();
     let query = Query {
+      explain: false,
+      shell: InitShell::Bash,
       alias: "dk".to_string(),
       params: vec!["cmp".to_string(), "ud".to_string()],
     };
 
-    let order = query.build_order(&db).unwrap();
-    assert_eq!(order.join(" "), String::from("docker compose up -d"));
+    let (order, _) = query.expand(record(&db, &query)).unwrap();
+    assert_eq!(
+      query.render(&order, Quoting::Escape).unwrap(),
+      String::from("docker compose up -d")
+    );
   }
 
   #[test]
   fn test_docker_image_ls() {
     let db = DummyDatabase::open().unwrap();
     let query = Query {
+      explain: false,
+      shell: InitShell::Bash,
       alias: "dk".to_string(),
       params: vec!["i".to_string(), "l".to_string()],
     };
 
-    let order = query.build_order(&db).unwrap();
-    assert_eq!(order.join(" "), String::from("docker image ls"));
+    let (order, _) = query.expand(record(&db, &query)).unwrap();
+    assert_eq!(
+      query.render(&order, Quoting::Escape).unwrap(),
+      String::from("docker image ls")
+    );
   }
 
   #[test]
   fn test_cargo_add() {
     let db = DummyDatabase::open().unwrap();
     let query = Query {
+      explain: false,
+      shell: InitShell::Bash,
       alias: "ca".to_string(),
       params: vec![
         "a/3".to_string(),
@@ -137,7 +872,595 @@ mod tests {
       ],
     };
 
-    let order = query.build_order(&db).unwrap();
-    assert_eq!(order.join(" "), String::from("cargo add serde tokio clap"));
+    let (order, _) = query.expand(record(&db, &query)).unwrap();
+    assert_eq!(
+      query.render(&order, Quoting::Escape).unwrap(),
+      String::from("cargo add serde tokio clap")
+    );
+  }
+
+  #[test]
+  fn test_cargo_add_rest() {
+    let db = DummyDatabase::open().unwrap();
+    let expand = |params: &[&str]| expand(&db, "ca", params);
+
+    assert_eq!(
+      expand(&["a/*", "serde", "a", "-F"]).unwrap(),
+      "cargo add serde a -F"
+    );
+    assert_eq!(expand(&["a/*"]).unwrap(), "cargo add");
+    assert_eq!(expand(&["a/0", "a"]).unwrap(), "cargo add add");
+
+    let err = expand(&["a/5", "serde"]).unwrap_err();
+    assert_eq!(err.to_string(), "`a/5` takes 5 arguments, got 1");
+    let err = expand(&["a/1"]).unwrap_err();
+    assert_eq!(err.to_string(), "`a/1` takes 1 argument, got 0");
+  }
+
+  #[test]
+  fn test_escaped_group() {
+    let db = DummyDatabase::open().unwrap();
+    let query = Query {
+      shell: InitShell::Nushell,
+      explain: false,
+      alias: "e".to_string(),
+      params: vec![r"a\/3".to_string(), r"src\/*".to_string(), "b".to_string()],
+    };
+
+    let (order, _) = query.expand(record(&db, &query)).unwrap();
+    assert_eq!(
+      query.render(&order, Quoting::Escape).unwrap(),
+      String::from(r#"["echo","a/3","src/*","b"]"#)
+    );
+  }
+
+  #[test]
+  fn test_quote_user_params() {
+    let db = DummyDatabase::open().unwrap();
+    let query = Query {
+      explain: false,
+      shell: InitShell::Bash,
+      alias: "e".to_string(),
+      params: vec![
+        "hello world".to_string(),
+        "it's \"quoted\"".to_string(),
+        "*.rs".to_string(),
+        "$(rm -rf ~)".to_string(),
+      ],
+    };
+
+    let (order, _) = query.expand(record(&db, &query)).unwrap();
+    assert_eq!(
+      query.render(&order, Quoting::Escape).unwrap(),
+      String::from(
+        r#"echo hello\ world it\'s\ \"quoted\" \*.rs \$\(rm\ -rf\ \~\)"#
+      )
+    );
+  }
+
+  #[test]
+  fn test_keep_stored_origins() {
+    let db = DummyDatabase::open().unwrap();
+    let query = Query {
+      explain: false,
+      shell: InitShell::Zsh,
+      alias: "dk".to_string(),
+      params: vec!["ud".to_string(), "my service".to_string()],
+    };
+
+    let (order, _) = query.expand(record(&db, &query)).unwrap();
+    assert_eq!(
+      query.render(&order, Quoting::Escape).unwrap(),
+      String::from(r"docker up -d my\ service")
+    );
+  }
+
+  #[test]
+  fn test_round_trip_bash() {
+    let db = DummyDatabase::open().unwrap();
+    let params = vec![
+      "hello world".to_string(),
+      "'single' \"double\"".to_string(),
+      "*".to_string(),
+      "[a-z]?.rs".to_string(),
+      "$HOME ${PATH} $(id) `id`".to_string(),
+      "~".to_string(),
+      "a=b;c|d&e>f<g".to_string(),
+      "back\\slash".to_string(),
+      "new\nline\ttab".to_string(),
+      "".to_string(),
+    ];
+    let query = Query {
+      shell: InitShell::Bash,
+      explain: false,
+      alias: "e".to_string(),
+      params,
+    };
+
+    let (order, _) = query.expand(record(&db, &query)).unwrap();
+    assert_eq!(
+      eval_bash(&query.render(&order, Quoting::Escape).unwrap()),
+      query.params
+    );
+  }
+
+  #[test]
+  fn test_quote_powershell() {
+    let db = DummyDatabase::open().unwrap();
+    let query = Query {
+      explain: false,
+      shell: InitShell::Powershell,
+      alias: "e".to_string(),
+      params: vec![
+        "hello world".to_string(),
+        "it's".to_string(),
+        "$env:PATH".to_string(),
+        "a,b".to_string(),
+        "".to_string(),
+      ],
+    };
+
+    let (order, _) = query.expand(record(&db, &query)).unwrap();
+    assert_eq!(
+      query.render(&order, Quoting::Escape).unwrap(),
+      String::from("echo 'hello world' 'it''s' '$env:PATH' 'a,b' ''")
+    );
+  }
+
+  #[test]
+  fn test_argv_nushell() {
+    let db = DummyDatabase::open().unwrap();
+    let query = Query {
+      explain: false,
+      shell: InitShell::Nushell,
+      alias: "dk".to_string(),
+      params: vec![
+        "cmp".to_string(),
+        "ud".to_string(),
+        "my \"service\"".to_string(),
+      ],
+    };
+
+    let (order, _) = query.expand(record(&db, &query)).unwrap();
+    assert_eq!(
+      query.render(&order, Quoting::Escape).unwrap(),
+      String::from(r#"["docker","compose","up","-d","my \"service\""]"#)
+    );
+  }
+
+  #[test]
+  fn test_defaults() {
+    let mut db = DummyDatabase::open().unwrap();
+    db.add_record(Record {
+      origin: "docker compose logs -f".into(),
+      alias: "dcl".into(),
+      mappings: vec![("w".into(), "worker".into())],
+      defaults: vec!["web".into(), "--tail=10".into()],
+      combined_flags: false,
+    });
+    let expand = |params: &[&str]| expand(&db, "dcl", params).unwrap();
+
+    assert_eq!(expand(&[]), "docker compose logs -f web --tail=10");
+    assert_eq!(expand(&["w"]), "docker compose logs -f worker --tail=10");
+    assert_eq!(expand(&["api", "-t"]), "docker compose logs -f api -t");
+    assert_eq!(expand(&["a", "b", "c"]), "docker compose logs -f a b c");
+  }
+
+  #[test]
+  fn test_defaults_count_typed_arguments() {
+    let mut db = DummyDatabase::open().unwrap();
+    db.add_record(Record {
+      origin: "kubectl -n {ns} logs".into(),
+      alias: "kl".into(),
+      mappings: vec![("-a".into(), "--all".into()), ("-q".into(), "-q".into())],
+      defaults: vec!["api-0".into(), "--tail=10".into()],
+      combined_flags: true,
+    });
+    db.add_record(Record {
+      origin: "git show {1}".into(),
+      alias: "gsh".into(),
+      mappings: Vec::new(),
+      defaults: vec!["HEAD".into()],
+      combined_flags: false,
+    });
+    let expand = |alias: &str, params: &[&str]| expand(&db, alias, params);
+
+    assert_eq!(
+      expand("kl", &["ns=prod"]).unwrap(),
+      "kubectl -n prod logs api-0 --tail=10"
+    );
+    assert_eq!(
+      expand("kl", &["ns=prod", "-aq"]).unwrap(),
+      "kubectl -n prod logs --all -q --tail=10"
+    );
+    assert_eq!(
+      expand("kl", &["c/1", "web", "ns=prod"]).unwrap(),
+      "kubectl -n prod logs c web"
+    );
+    // Defaults come after the placeholders, so they can't fill them.
+    let err = expand("gsh", &[]).unwrap_err();
+    assert_eq!(err.to_string(), "alias `gsh` requires argument {1}");
+    assert_eq!(expand("gsh", &["v1"]).unwrap(), "git show v1");
+  }
+
+  #[test]
+  fn test_explain() {
+    let mut db = DummyDatabase::open().unwrap();
+    db.add_record(Record {
+      origin: "cargo".into(),
+      alias: "ca".into(),
+      mappings: vec![("a".into(), "add".into()), ("r".into(), "run".into())],
+      defaults: vec!["--help".into(), "-q".into(), "-v".into(), "-r".into()],
+      combined_flags: false,
+    });
+    let query = Query {
+      shell: InitShell::Bash,
+      explain: true,
+      alias: "ca".to_string(),
+      params: vec!["a/1".to_string(), "r".to_string(), "x".to_string()],
+    };
+
+    let record = record(&db, &query);
+    let (order, steps) = query.expand(record).unwrap();
+    assert_eq!(
+      explain(record, record, &steps),
+      vec![
+        "ca: cargo",
+        "  a/1  mapped to `add`, takes the next argument as it is",
+        "  r    consumed by `a/1`",
+        "  x    passed through",
+        "  -r   default for argument 4",
+      ]
+    );
+    assert_eq!(
+      query.render(&order, Quoting::Escape).unwrap(),
+      String::from("cargo add r x -r")
+    );
+  }
+
+  #[test]
+  fn test_scoped_mappings() {
+    let mut db = DummyDatabase::open().unwrap();
+    db.add_params(
+      "dk",
+      [
+        ("logs", "compose.l"),
+        ("--latest", "compose.logs.l"),
+        ("list", "image.l"),
+        ("Dockerfile.dev", r"df\.dev"),
+      ]
+      .into_iter(),
+    );
+    let expand = |params: &[&str]| expand(&db, "dk", params).unwrap();
+
+    assert_eq!(expand(&["l"]), "docker ls");
+    assert_eq!(expand(&["i", "l"]), "docker image list");
+    assert_eq!(expand(&["cmp", "l", "l"]), "docker compose logs --latest");
+    assert_eq!(expand(&["cmp", "ud", "l"]), "docker compose up -d logs");
+    assert_eq!(expand(&["cmp", "i", "l"]), "docker compose image list");
+    assert_eq!(expand(&["compose", "l"]), "docker compose logs");
+    // An escaped dot is part of the parameter.
+    assert_eq!(expand(&["df.dev"]), "docker Dockerfile.dev");
+  }
+
+  #[test]
+  fn test_multi_word_mappings() {
+    let mut db = DummyDatabase::open().unwrap();
+    db.add_record(Record {
+      origin: "docker".into(),
+      alias: "dk".into(),
+      mappings: vec![
+        ("cmp".into(), "compose".into()),
+        ("up".into(), "up".into()),
+        ("d".into(), "down".into()),
+        ("up d".into(), "up --detach".into()),
+        ("up d b".into(), "up --detach --build".into()),
+        ("compose.up d".into(), "up --wait".into()),
+      ],
+      defaults: Vec::new(),
+      combined_flags: false,
+    });
+    let expand = |params: &[&str]| expand(&db, "dk", params).unwrap();
+
+    assert_eq!(expand(&["up"]), "docker up");
+    assert_eq!(
+      expand(&["up", "d", "b", "d"]),
+      "docker up --detach --build down"
+    );
+    assert_eq!(expand(&["up", "d", "x"]), "docker up --detach x");
+    assert_eq!(expand(&["up", "x", "d"]), "docker up x down");
+    assert_eq!(expand(&["up d"]), r"docker up\ d");
+    assert_eq!(expand(&["cmp", "up", "d"]), "docker compose up --wait");
+    assert_eq!(
+      expand(&["cmp", "up", "d", "b"]),
+      "docker compose up --detach --build"
+    );
+
+    // A key spanning several words is explained as a single step.
+    let query = Query {
+      shell: InitShell::Bash,
+      explain: false,
+      alias: "dk".to_string(),
+      params: ["up", "d", "b", "d"].map(String::from).to_vec(),
+    };
+    let (_, steps) = query.expand(record(&db, &query)).unwrap();
+    let keys = steps.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+    assert_eq!(keys, vec!["up d b", "d"]);
+  }
+
+  #[test]
+  fn test_combined_flags() {
+    let mut db = DummyDatabase::open().unwrap();
+    db.add_record(Record {
+      origin: "docker".into(),
+      alias: "dk".into(),
+      mappings: vec![
+        ("-a".into(), "--all".into()),
+        ("q".into(), "--quiet".into()),
+        ("-f".into(), "--filter".into()),
+        ("s".into(), "--size".into()),
+      ],
+      defaults: Vec::new(),
+      combined_flags: true,
+    });
+    let expand =
+      |db: &DummyDatabase, params: &[&str]| expand(db, "dk", params).unwrap();
+
+    assert_eq!(expand(&db, &["ps", "-aq"]), "docker ps --all --quiet");
+    assert_eq!(
+      expand(&db, &["ps", "-qaf"]),
+      "docker ps --quiet --all --filter"
+    );
+    assert_eq!(expand(&db, &["ps", "-sq"]), "docker ps --size --quiet");
+    // Only words starting with a dash are split, even if each letter is
+    // mapped.
+    assert_eq!(expand(&db, &["ps", "qs"]), "docker ps qs");
+    assert_eq!(expand(&db, &["ps", "-ax"]), "docker ps -ax");
+    assert_eq!(expand(&db, &["ps", "--aq"]), "docker ps --aq");
+
+    db.set_combined_flags("dk", false);
+    assert_eq!(expand(&db, &["ps", "-aq"]), "docker ps -aq");
+  }
+
+  fn nested_db(records: &[(&str, &str)]) -> DummyDatabase {
+    let mut db = DummyDatabase::open().unwrap();
+    for (alias, origin) in records {
+      db.add_record(Record {
+        origin: origin.to_string().into(),
+        alias: alias.to_string().into(),
+        mappings: vec![("b".into(), "build".into())],
+        defaults: Vec::new(),
+        combined_flags: false,
+      });
+    }
+    db
+  }
+
+  #[test]
+  fn test_nested() {
+    let db = nested_db(&[("dc", "@dk cmp"), ("dcu", "@dc ud --wait")]);
+
+    assert_eq!(
+      expand(&db, "dc", &["ud", "b"]).unwrap(),
+      "docker compose up -d build"
+    );
+    assert_eq!(
+      expand(&db, "dc", &["i", "l"]).unwrap(),
+      "docker compose image ls"
+    );
+    assert_eq!(
+      expand(&db, "dcu", &["api"]).unwrap(),
+      "docker compose up -d --wait api"
+    );
+  }
+
+  #[test]
+  fn test_nested_scopes() {
+    let mut db = nested_db(&[("dcl", "@dk cmp l"), ("dud", "@dk up d")]);
+    db.add_params(
+      "dk",
+      [("logs", "compose.l"), ("up --detach", "up d")].into_iter(),
+    );
+
+    assert_eq!(expand(&db, "dcl", &[]).unwrap(), "docker compose logs");
+    assert_eq!(expand(&db, "dud", &[]).unwrap(), "docker up --detach");
+  }
+
+  #[test]
+  fn test_nested_multi_word() {
+    let mut db = nested_db(&[("dc", "@dk cmp")]);
+    db.add_params("dk", [("up --detach", "up d")].into_iter());
+    db.add_params("dc", [("up --detach --build", "up d b")].into_iter());
+
+    // Keys spanning several words are inherited, the longest one winning
+    // whichever alias defines it.
+    assert_eq!(
+      expand(&db, "dc", &["up", "d"]).unwrap(),
+      "docker compose up --detach"
+    );
+    assert_eq!(
+      expand(&db, "dc", &["up", "d", "b"]).unwrap(),
+      "docker compose up --detach --build"
+    );
+    assert_eq!(
+      expand(&db, "dc", &["up", "b"]).unwrap(),
+      "docker compose up build"
+    );
+  }
+
+  #[test]
+  fn test_nested_errors() {
+    let db =
+      nested_db(&[("a", "@b x"), ("b", "@c"), ("c", "@a"), ("d", "@missing")]);
+    let err = expand(&db, "a", &[]).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "alias `a` references itself: a -> b -> c -> a"
+    );
+    let err = expand(&db, "d", &[]).unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "alias `d` references `missing`, which doesn't exist"
+    );
+
+    let chain = (0..10)
+      .map(|idx| (format!("n{idx}"), format!("@n{}", idx + 1)))
+      .collect::<Vec<_>>();
+    let chain =
+      chain.iter().map(|(a, o)| (a.as_str(), o.as_str())).collect::<Vec<_>>();
+    let err = expand(&nested_db(&chain), "n0", &[]).unwrap_err();
+    assert!(
+      err
+        .to_string()
+        .starts_with("alias `n0` references more than 8 aliases in a row"),
+      "{err}"
+    );
+  }
+
+  fn template_db() -> DummyDatabase {
+    DummyDatabase {
+      records: vec![
+        Record {
+          origin: "git log --oneline {1}..{2}".into(),
+          alias: "glr".into(),
+          mappings: vec![("m".into(), "main".into())],
+          defaults: Vec::new(),
+          combined_flags: false,
+        },
+        Record {
+          origin: "kubectl -n {ns} logs {1}".into(),
+          alias: "kl".into(),
+          mappings: Vec::new(),
+          defaults: Vec::new(),
+          combined_flags: false,
+        },
+      ],
+    }
+  }
+
+  #[test]
+  fn test_parse_template() {
+    assert_eq!(
+      parse_template("find {} -name {1} -exec ${EDITOR} {x-y} {0} {"),
+      vec![
+        Part::Text("find {} -name "),
+        Part::Index(1),
+        Part::Text(" -exec ${EDITOR} "),
+        Part::Name("x-y"),
+        Part::Text(" {0} {"),
+      ]
+    );
+    assert_eq!(parse_template("echo"), vec![Part::Text("echo")]);
+    assert_eq!(
+      parse_template("git show HEAD@{{{1}}}"),
+      vec![Part::Text("git show HEAD@{"), Part::Index(1), Part::Text("}"),]
+    );
+    assert_eq!(
+      parse_template("awk '{print $1}' {{x}}"),
+      vec![Part::Text("awk '{print $1}' {"), Part::Text("x}"),]
+    );
+  }
+
+  #[test]
+  fn test_numbered_placeholders() {
+    let db = template_db();
+    let query = Query {
+      explain: false,
+      shell: InitShell::Bash,
+      alias: "glr".to_string(),
+      params: vec!["m".to_string(), "my branch".to_string(), "-p".to_string()],
+    };
+
+    let (order, _) = query.expand(record(&db, &query)).unwrap();
+    assert_eq!(
+      query.render(&order, Quoting::Escape).unwrap(),
+      String::from(r"git log --oneline main..my\ branch -p")
+    );
+  }
+
+  #[test]
+  fn test_named_placeholders() {
+    let db = template_db();
+    let query = Query {
+      explain: false,
+      shell: InitShell::Nushell,
+      alias: "kl".to_string(),
+      params: vec![
+        "api-0".to_string(),
+        "ns=prod".to_string(),
+        "--tail=10".to_string(),
+      ],
+    };
+
+    let (order, _) = query.expand(record(&db, &query)).unwrap();
+    assert_eq!(
+      query.render(&order, Quoting::Escape).unwrap(),
+      String::from(r#"["kubectl","-n","prod","logs","api-0","--tail=10"]"#)
+    );
+  }
+
+  #[test]
+  fn test_explain_placeholders() {
+    let db = template_db();
+    let query = Query {
+      explain: true,
+      shell: InitShell::Bash,
+      alias: "glr".to_string(),
+      params: vec!["-p".to_string(), "m".to_string(), "v1".to_string()],
+    };
+
+    let glr = record(&db, &query);
+    let (_, steps) = query.expand(glr).unwrap();
+    assert_eq!(
+      explain(glr, glr, &steps),
+      vec![
+        "glr: git log --oneline {1}..{2}",
+        "  -p  fills {1}",
+        "  m   mapped to `main`, fills {2}",
+        "  v1  passed through",
+      ]
+    );
+
+    let query = Query {
+      explain: true,
+      shell: InitShell::Bash,
+      alias: "kl".to_string(),
+      params: vec!["ns=prod".to_string(), "api-0".to_string()],
+    };
+
+    let kl = record(&db, &query);
+    let (_, steps) = query.expand(kl).unwrap();
+    assert_eq!(
+      explain(kl, kl, &steps),
+      vec![
+        "kl: kubectl -n {ns} logs {1}",
+        "  ns=prod  fills {ns}",
+        "  api-0    fills {1}",
+      ]
+    );
+  }
+
+  #[test]
+  fn test_missing_placeholder() {
+    let db = template_db();
+    let query = Query {
+      explain: false,
+      shell: InitShell::Bash,
+      alias: "kl".to_string(),
+      params: vec!["api-0".to_string()],
+    };
+
+    let err = query.expand(record(&db, &query)).unwrap_err();
+    assert_eq!(err.to_string(), "alias `kl` requires `ns=<value>` for {ns}");
+
+    let query = Query {
+      explain: false,
+      shell: InitShell::Bash,
+      alias: "glr".to_string(),
+      params: vec!["v1".to_string()],
+    };
+
+    let err = query.expand(record(&db, &query)).unwrap_err();
+    assert_eq!(err.to_string(), "alias `glr` requires argument {2}");
   }
 }
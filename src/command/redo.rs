@@ -0,0 +1,40 @@
+use crate::command::history::time_ago;
+use crate::command::Execute;
+use crate::database::{Database, DbOperation};
+use crate::error::BrokenPipeHandler;
+use anyhow::{ensure, Result};
+use clap::Parser;
+use std::io::{self, Write};
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Parser)]
+pub struct Redo {
+  /// Number of undone changes to restore, see `abbr history`
+  #[arg(default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+  pub steps: u64,
+}
+
+impl Execute for Redo {
+  fn execute(&self) -> Result<()> {
+    let db = Database::open()?;
+    let history = db.history()?;
+    // The states undone are the ones newer than the restored one.
+    let undone = db.position(&history)?.unwrap_or(0);
+    let steps = self.steps as usize;
+    ensure!(
+      steps <= undone,
+      "cannot redo {steps} change(s), only {undone} undone"
+    );
+
+    let snapshot = &history[undone - steps];
+    db.restore(snapshot)?;
+    writeln!(
+      io::stdout(),
+      "restored database from {}",
+      time_ago(snapshot.time)
+    )
+    .pipe_exit("stdout")?;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,133 @@
+use crate::command::Execute;
+use crate::database::{Database, DbOperation, Record};
+use crate::error::BrokenPipeHandler;
+use crate::project::{self, Layer, Project};
+use crate::util::{self, ALIAS_DELIMITER};
+use anyhow::{bail, Result};
+use clap::Parser;
+use std::io::{self, Write};
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Parser)]
+pub struct Show {
+  /// Alias to show
+  pub alias: String,
+}
+
+impl Execute for Show {
+  fn execute(&self) -> Result<()> {
+    let project = Project::find();
+    let db = Database::open_readonly()?;
+    let records = project::layer(project.as_ref(), db.records());
+    let handle = &mut io::stdout();
+    for line in self.describe(&records)? {
+      writeln!(handle, "{line}").pipe_exit("stdout")?;
+    }
+
+    Ok(())
+  }
+}
+
+impl Show {
+  fn describe(&self, records: &[(Layer, &Record)]) -> Result<Vec<String>> {
+    let alias = self.alias.as_str();
+    let Some((layer, record)) = records.iter().find(|(_, r)| r.alias == alias)
+    else {
+      let aliases = records.iter().map(|(_, r)| r.alias.as_ref());
+      let hint = util::did_you_mean(&util::closest(alias, aliases));
+      bail!("alias `{alias}` not found{hint}");
+    };
+
+    let mut lines = vec![
+      format!("alias:     {}", record.alias),
+      format!("command:   {}", record.origin),
+      format!("source:    {layer}"),
+    ];
+    let mut mappings = record.mappings.iter().collect::<Vec<_>>();
+    mappings.sort_unstable();
+    // Print parameters in the same `<origin>=<alias>` format they are entered
+    // with, one per line since origins may contain spaces.
+    for (idx, (alias, origin)) in mappings.into_iter().enumerate() {
+      let label = if idx == 0 { "mappings:" } else { "" };
+      lines.push(format!("{label:9}  {origin}{ALIAS_DELIMITER}{alias}"));
+    }
+    if !record.defaults.is_empty() {
+      lines.push(format!("defaults:  {}", record.defaults.join(" ")));
+    }
+    if record.combined_flags {
+      lines.push("flags:     combined".to_owned());
+    }
+
+    Ok(lines)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::command::show::Show;
+  use crate::database::{test::DummyDatabase, DbOperation, Record};
+  use crate::project::{self, test::write_project, Project};
+
+  #[test]
+  fn test_show() {
+    let mut db = DummyDatabase::open().unwrap();
+    db.add_record(Record {
+      origin: "docker compose logs -f".into(),
+      alias: "dcl".into(),
+      mappings: vec![("w".into(), "worker".into())],
+      defaults: vec!["web".into(), "--tail=10".into()],
+      combined_flags: false,
+    });
+    let records = project::layer(None, db.records());
+
+    let show = Show { alias: "dk".to_string() };
+    assert_eq!(
+      show.describe(&records).unwrap(),
+      vec![
+        "alias:     dk",
+        "command:   docker",
+        "source:    global",
+        "mappings:  compose=cmp",
+        "           image=i",
+        "           ls=l",
+        "           up -d=ud",
+      ]
+    );
+
+    let show = Show { alias: "dcl".to_string() };
+    assert_eq!(
+      show.describe(&records).unwrap(),
+      vec![
+        "alias:     dcl",
+        "command:   docker compose logs -f",
+        "source:    global",
+        "mappings:  worker=w",
+        "defaults:  web --tail=10",
+      ]
+    );
+
+    let show = Show { alias: "dcll".to_string() };
+    let err = show.describe(&records).unwrap_err();
+    assert_eq!(err.to_string(), "alias `dcll` not found, did you mean `dcl`?");
+  }
+
+  #[test]
+  fn test_show_project() {
+    let dir = tempfile::tempdir().unwrap();
+    let trust = write_project(
+      dir.path(),
+      "[[records]]\nalias = \"e\"\norigin = \"echo project\"\n",
+    );
+    let project = Project::find_from(dir.path(), &trust).unwrap();
+    let db = DummyDatabase::open().unwrap();
+    let records = project::layer(project.as_ref(), db.records());
+
+    let show = Show { alias: "e".to_string() };
+    let lines = show.describe(&records).unwrap();
+    assert_eq!(lines[1], "command:   echo project");
+    assert_eq!(
+      lines[2],
+      format!("source:    {}", dir.path().join(".abbr.toml").display())
+    );
+  }
+}
@@ -0,0 +1,41 @@
+use crate::command::history::time_ago;
+use crate::command::Execute;
+use crate::database::{Database, DbOperation};
+use crate::error::BrokenPipeHandler;
+use anyhow::{ensure, Result};
+use clap::Parser;
+use std::io::{self, Write};
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Parser)]
+pub struct Undo {
+  /// Number of changes to undo, see `abbr history`
+  #[arg(default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+  pub steps: u64,
+}
+
+impl Execute for Undo {
+  fn execute(&self) -> Result<()> {
+    let db = Database::open()?;
+    let history = db.history()?;
+    // Right after an undo, go further back from the state it restored.
+    let start = db.position(&history)?.map_or(0, |idx| idx + 1);
+    let steps = self.steps as usize;
+    let kept = history.len() - start;
+    ensure!(
+      steps <= kept,
+      "cannot undo {steps} change(s), only {kept} previous state(s) kept"
+    );
+
+    let snapshot = &history[start + steps - 1];
+    db.restore(snapshot)?;
+    writeln!(
+      io::stdout(),
+      "restored database from {}",
+      time_ago(snapshot.time)
+    )
+    .pipe_exit("stdout")?;
+
+    Ok(())
+  }
+}
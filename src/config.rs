@@ -1,20 +1,339 @@
 use std::env;
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 
-use anyhow::{ensure, Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::util;
 
 pub const DATA_DIR: &str = "_ABBR_DATA_DIR";
 
-pub fn data_dir() -> Result<PathBuf> {
-  let dir = match env::var_os(DATA_DIR) {
-    Some(dir) => PathBuf::from(dir),
-    None => dirs::data_local_dir()
-      .context(format!(
+/// Settings read from `config.toml`, each of which can be overridden by an
+/// environment variable.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub alias: Option<String>,
+  pub quoting: Quoting,
+  pub fuzzy: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub data_dir: Option<PathBuf>,
+  /// Profile whose database is used, the default one if unset.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub profile: Option<String>,
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(
+  ValueEnum, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+#[value(rename_all = "kebab-case")]
+pub enum Quoting {
+  /// Quote arguments so that the shell sees them verbatim
+  #[default]
+  Escape,
+  /// Pass arguments through as typed, so that the shell evaluates them again
+  Raw,
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+#[value(rename_all = "kebab-case")]
+pub enum Key {
+  /// Name of the command defined by `abbr init`
+  Alias,
+  /// How arguments are quoted in expanded commands
+  Quoting,
+  /// Whether a mistyped alias expands to the closest existing one
+  Fuzzy,
+  /// Directory in which the databases are stored
+  DataDir,
+  /// Profile whose database is used
+  Profile,
+}
+
+impl Key {
+  pub const ALL: [Key; 5] =
+    [Key::Alias, Key::Quoting, Key::Fuzzy, Key::DataDir, Key::Profile];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Key::Alias => "alias",
+      Key::Quoting => "quoting",
+      Key::Fuzzy => "fuzzy",
+      Key::DataDir => "data-dir",
+      Key::Profile => "profile",
+    }
+  }
+
+  /// Environment variable overriding the setting.
+  pub fn env(self) -> &'static str {
+    match self {
+      Key::Alias => "_ABBR_ALIAS",
+      Key::Quoting => "_ABBR_QUOTING",
+      Key::Fuzzy => "_ABBR_FUZZY",
+      Key::DataDir => DATA_DIR,
+      Key::Profile => "_ABBR_PROFILE",
+    }
+  }
+}
+
+impl Config {
+  pub const DEFAULT_ALIAS: &str = "ab";
+  pub const DEFAULT_PROFILE: &str = "default";
+
+  /// Reads the configuration file, then applies environment overrides.
+  pub fn load() -> Result<Self> {
+    let mut config = Self::read(&config_path()?)?;
+    config.apply_env(env::var_os)?;
+    Ok(config)
+  }
+
+  pub fn read(path: &Path) -> Result<Self> {
+    match fs::read_to_string(path) {
+      Ok(contents) => toml::from_str(&contents).with_context(|| {
+        format!("invalid configuration file: {}", path.display())
+      }),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+      Err(e) => Err(e).with_context(|| {
+        format!("failed to read configuration file: {}", path.display())
+      }),
+    }
+  }
+
+  pub fn write(&self, path: &Path) -> Result<()> {
+    let contents =
+      toml::to_string(self).context("failed to serialize configuration")?;
+    let dir = path.parent().unwrap();
+    fs::create_dir_all(dir).with_context(|| {
+      format!("unable to create config directory: {}", dir.display())
+    })?;
+    util::write(path, contents).context("failed to write configuration file")
+  }
+
+  fn apply_env(
+    &mut self,
+    var: impl Fn(&'static str) -> Option<OsString>,
+  ) -> Result<()> {
+    for key in Key::ALL {
+      let Some(value) = var(key.env()) else {
+        continue;
+      };
+      // Paths don't have to be valid UTF-8.
+      if key == Key::DataDir {
+        self.data_dir = Some(value.into());
+        continue;
+      }
+      let value = value.to_str().unwrap_or_default();
+      self.set(key, value).with_context(|| format!("invalid {}", key.env()))?;
+    }
+    Ok(())
+  }
+
+  /// The value of `key`, or its default if it isn't set.
+  pub fn get(&self, key: Key) -> Result<String> {
+    let value = match key {
+      Key::Alias => {
+        self.alias.as_deref().unwrap_or(Self::DEFAULT_ALIAS).to_owned()
+      }
+      Key::Quoting => {
+        self.quoting.to_possible_value().unwrap().get_name().into()
+      }
+      Key::Fuzzy => self.fuzzy.to_string(),
+      Key::DataDir => self.data_dir()?.display().to_string(),
+      Key::Profile => {
+        self.profile.as_deref().unwrap_or(Self::DEFAULT_PROFILE).to_owned()
+      }
+    };
+    Ok(value)
+  }
+
+  pub fn set(&mut self, key: Key, value: &str) -> Result<()> {
+    match key {
+      Key::Alias => {
+        ensure!(
+          !value.is_empty() && value.chars().all(is_name_char),
+          "invalid alias `{value}`, must only contain letters, digits, `-` or `_`"
+        );
+        self.alias = Some(value.to_owned());
+      }
+      Key::Quoting => {
+        self.quoting = Quoting::from_str(value, false).map_err(|_| {
+          anyhow!("invalid quoting `{value}`, must be `escape` or `raw`")
+        })?;
+      }
+      Key::Fuzzy => {
+        self.fuzzy = match value {
+          "true" | "1" | "on" => true,
+          "false" | "0" | "off" => false,
+          _ => bail!("invalid fuzzy `{value}`, must be `true` or `false`"),
+        };
+      }
+      Key::DataDir => {
+        let dir = PathBuf::from(value);
+        ensure!(dir.is_absolute(), "data-dir must be an absolute path");
+        self.data_dir = Some(dir);
+      }
+      Key::Profile => {
+        validate_profile(value)?;
+        self.profile =
+          (value != Self::DEFAULT_PROFILE).then(|| value.to_owned());
+      }
+    }
+    Ok(())
+  }
+
+  pub fn unset(&mut self, key: Key) {
+    match key {
+      Key::Alias => self.alias = None,
+      Key::Quoting => self.quoting = Quoting::default(),
+      Key::Fuzzy => self.fuzzy = false,
+      Key::DataDir => self.data_dir = None,
+      Key::Profile => self.profile = None,
+    }
+  }
+
+  /// Directory in which the databases of all profiles are stored.
+  pub fn data_dir(&self) -> Result<PathBuf> {
+    let dir = match &self.data_dir {
+      Some(dir) => dir.clone(),
+      None => home_data_dir().context(format!(
         "failed to find data directory, please set {DATA_DIR} manually"
-      ))?
-      .join("abbreviator"),
-  };
+      ))?,
+    };
+
+    ensure!(dir.is_absolute(), format!("{DATA_DIR} must be an absolute path"));
+    Ok(dir)
+  }
+
+  /// Directory in which the database of the active profile is stored.
+  pub fn profile_dir(&self) -> Result<PathBuf> {
+    let data_dir = self.data_dir()?;
+    let Some(profile) = &self.profile else {
+      return Ok(data_dir);
+    };
+
+    let dir = profiles_dir(&data_dir).join(profile);
+    ensure!(
+      dir.is_dir(),
+      "profile `{profile}` does not exist, create it with `abbr profile create {profile}`"
+    );
+    Ok(dir)
+  }
+}
+
+/// Directory holding the named profiles, the default one being stored in the
+/// data directory itself.
+pub fn profiles_dir(data_dir: &Path) -> PathBuf {
+  data_dir.join("profiles")
+}
+
+pub fn validate_profile(name: &str) -> Result<()> {
+  ensure!(
+    !name.is_empty() && name.chars().all(is_name_char),
+    "invalid profile `{name}`, must only contain letters, digits, `-` or `_`"
+  );
+  Ok(())
+}
+
+fn is_name_char(c: char) -> bool {
+  c.is_alphanumeric() || matches!(c, '-' | '_')
+}
+
+pub fn config_path() -> Result<PathBuf> {
+  let dir = dirs::config_dir().context("failed to find config directory")?;
+  Ok(dir.join("abbreviator").join("config.toml"))
+}
+
+/// Directory in which the database of the active profile is stored.
+pub fn data_dir() -> Result<PathBuf> {
+  Config::load()?.profile_dir()
+}
+
+/// The user's own data directory, which [`DATA_DIR`] doesn't change.
+pub fn home_data_dir() -> Option<PathBuf> {
+  dirs::data_local_dir().map(|dir| dir.join("abbreviator"))
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::config::{profiles_dir, Config, Key, Quoting};
+  use std::ffi::OsString;
+  use std::fs;
+
+  #[test]
+  fn test_read_write() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("abbreviator/config.toml");
+    assert_eq!(Config::read(&path).unwrap(), Config::default());
+
+    let mut config = Config::default();
+    config.set(Key::Alias, "aa").unwrap();
+    config.set(Key::Quoting, "raw").unwrap();
+    config.set(Key::Fuzzy, "true").unwrap();
+    config.write(&path).unwrap();
+
+    let config = Config::read(&path).unwrap();
+    assert_eq!(config.alias.as_deref(), Some("aa"));
+    assert_eq!(config.quoting, Quoting::Raw);
+    assert!(config.fuzzy);
+    assert_eq!(config.get(Key::Quoting).unwrap(), "raw");
+  }
+
+  #[test]
+  fn test_invalid_values() {
+    let mut config = Config::default();
+
+    assert!(config.set(Key::Alias, "a b").is_err());
+    assert!(config.set(Key::Quoting, "always").is_err());
+    assert!(config.set(Key::Fuzzy, "maybe").is_err());
+    assert!(config.set(Key::DataDir, "relative").is_err());
+    assert!(config.set(Key::Profile, "../work").is_err());
+    assert_eq!(config, Config::default());
+  }
+
+  #[test]
+  fn test_env_overrides() {
+    let mut config = Config::default();
+    config.set(Key::Alias, "aa").unwrap();
+
+    let env = |name: &str| match name {
+      "_ABBR_ALIAS" => Some(OsString::from("bb")),
+      "_ABBR_FUZZY" => Some(OsString::from("1")),
+      _ => None,
+    };
+    config.apply_env(env).unwrap();
+    assert_eq!(config.get(Key::Alias).unwrap(), "bb");
+    assert!(config.fuzzy);
+
+    let env =
+      |name: &str| (name == "_ABBR_QUOTING").then(|| OsString::from("never"));
+    let err = config.apply_env(env).unwrap_err();
+    assert_eq!(err.to_string(), "invalid _ABBR_QUOTING");
+  }
+
+  #[test]
+  fn test_profile_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config =
+      Config { data_dir: Some(dir.path().to_owned()), ..Config::default() };
+    assert_eq!(config.profile_dir().unwrap(), dir.path());
+
+    config.set(Key::Profile, "work").unwrap();
+    let err = config.profile_dir().unwrap_err();
+    assert!(err.to_string().starts_with("profile `work` does not exist"));
+
+    fs::create_dir_all(profiles_dir(dir.path()).join("work")).unwrap();
+    assert_eq!(config.profile_dir().unwrap(), dir.path().join("profiles/work"));
 
-  ensure!(dir.is_absolute(), format!("{DATA_DIR} must be an absolute path"));
-  Ok(dir)
+    config.set(Key::Profile, "default").unwrap();
+    assert_eq!(config.profile, None);
+  }
 }
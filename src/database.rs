@@ -1,15 +1,25 @@
-use crate::{config, util};
-use anyhow::{bail, Context, Result};
+mod layout;
+mod migration;
+
+use crate::config::{self, Config};
+use crate::trust::{self, Trust};
+use crate::util;
+use anyhow::{bail, ensure, Context, Result};
 use bincode::Options;
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
+use memmap2::Mmap;
 use ouroboros::self_referencing;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::fs::{File, OpenOptions};
+use std::ops::Deref;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{fs, io};
 
 pub const EXCLUDED_CHARS: &[char] = &['\r', '\n'];
-type Str<'s> = Cow<'s, str>;
+pub(crate) type Str<'s> = Cow<'s, str>;
 
 pub trait DbOperation {
   fn open() -> Result<Self>
@@ -25,23 +35,66 @@ pub trait DbOperation {
   where
     S: AsRef<str>,
     P: Iterator<Item = S>;
+  fn set_defaults<S, D>(&mut self, alias: S, defaults: D)
+  where
+    S: AsRef<str> + Into<String>,
+    D: Iterator<Item = S>;
+  fn set_combined_flags<S>(&mut self, alias: S, enabled: bool)
+  where
+    S: AsRef<str>;
   fn del_record<S>(&mut self, alias: S)
   where
     S: AsRef<str>;
-  fn records(&self) -> &[Record];
+  fn records(&self) -> &[Record<'_>];
 }
 
 #[cfg_attr(debug_assertions, derive(Debug))]
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Record<'r> {
   pub origin: Str<'r>,
   pub alias: Str<'r>,
   pub mappings: Vec<(Str<'r>, Str<'r>)>,
+  /// Arguments appended when fewer are given, the N-th one filling in for a
+  /// missing N-th argument.
+  pub defaults: Vec<Str<'r>>,
+  /// Whether a dash followed by single-letter parameters, such as `-aq`, is
+  /// expanded letter by letter.
+  pub combined_flags: bool,
+}
+
+impl Record<'_> {
+  /// Whether both records expand the same way, ignoring the order of
+  /// mappings.
+  pub fn same_as(&self, other: &Record) -> bool {
+    let to_set = |mappings: &[(Str, Str)]| {
+      mappings
+        .iter()
+        .map(|(a, o)| (a.to_string(), o.to_string()))
+        .collect::<HashSet<_>>()
+    };
+    self.alias == other.alias
+      && self.origin == other.origin
+      && self.defaults == other.defaults
+      && self.combined_flags == other.combined_flags
+      && self.mappings.len() == other.mappings.len()
+      && to_set(&self.mappings) == to_set(&other.mappings)
+  }
 }
 
 #[self_referencing]
 pub struct Database {
   path: PathBuf,
+  /// Whether the database is outside the default data directory, so that it
+  /// has to be allowed before it is read.
+  external: bool,
+  /// Version the database was upgraded from when it was read, until the
+  /// original is backed up by [`Database::save`].
+  upgraded_from: Option<u32>,
+  /// Whether the database as read was saved into the history yet.
+  snapshotted: bool,
+  /// Exclusive lock on the data directory, held by writers until dropped.
+  /// Read-only databases don't take it and can't be saved.
+  lock: Option<File>,
   bytes: Vec<u8>,
   #[borrows(bytes)]
   #[covariant]
@@ -51,14 +104,31 @@ pub struct Database {
 impl DbOperation for Database {
   fn open() -> Result<Self> {
     let data_dir = config::data_dir()?;
+    let external = trust::is_external(&data_dir);
 
-    Self::open_dir(data_dir)
+    Self::open_dir(data_dir, true, external)
   }
 
   fn save(&mut self) -> Result<()> {
+    self.ensure_writable()?;
+    if let Some(version) = *self.borrow_upgraded_from() {
+      // Keep the database as it was before upgrading, since older versions of
+      // abbr can't read the new one.
+      let mut backup = self.borrow_path().clone().into_os_string();
+      backup.push(format!(".v{version}.bak"));
+      util::write(&backup, self.borrow_bytes())
+        .context("failed to back up database")?;
+      self.with_upgraded_from_mut(|upgraded_from| *upgraded_from = None);
+    }
+    if !*self.borrow_snapshotted() && !self.borrow_bytes().is_empty() {
+      self.snapshot().context("failed to save database history")?;
+      self.with_snapshotted_mut(|snapshotted| *snapshotted = true);
+    }
+
     let bytes = Self::serialize(self.records())?;
-    util::write(self.borrow_path(), bytes)
-      .context("failed to write database")?;
+    self.write(self.borrow_path(), &bytes)?;
+    // The database moved on from any state it was restored to.
+    _ = fs::remove_file(self.position_path());
     Ok(())
   }
 
@@ -110,6 +180,33 @@ impl DbOperation for Database {
     });
   }
 
+  fn set_defaults<S, D>(&mut self, alias: S, defaults: D)
+  where
+    S: AsRef<str> + Into<String>,
+    D: Iterator<Item = S>,
+  {
+    let alias = alias.as_ref();
+
+    self.with_records_mut(|records| {
+      if let Some(record) = records.iter_mut().find(|r| r.alias == alias) {
+        record.defaults = defaults.map(|d| d.into().into()).collect();
+      }
+    });
+  }
+
+  fn set_combined_flags<S>(&mut self, alias: S, enabled: bool)
+  where
+    S: AsRef<str>,
+  {
+    self.with_records_mut(|records| {
+      if let Some(record) =
+        records.iter_mut().find(|r| r.alias == alias.as_ref())
+      {
+        record.combined_flags = enabled;
+      }
+    });
+  }
+
   fn del_record<S>(&mut self, alias: S)
   where
     S: AsRef<str>,
@@ -121,26 +218,63 @@ impl DbOperation for Database {
     }
   }
 
-  fn records(&self) -> &[Record] {
+  fn records(&self) -> &[Record<'_>] {
     self.borrow_records()
   }
 }
 
 impl Database {
-  fn open_dir(data_dir: impl AsRef<Path>) -> Result<Self> {
+  /// Opens the database without taking the lock, so that it never waits for
+  /// writers. Since [`util::write`] replaces the file atomically, the records
+  /// read are always a complete state.
+  pub fn open_readonly() -> Result<Self> {
+    let data_dir = config::data_dir()?;
+    let external = trust::is_external(&data_dir);
+
+    Self::open_dir(data_dir, false, external)
+  }
+
+  fn open_dir(
+    data_dir: impl AsRef<Path>,
+    writable: bool,
+    external: bool,
+  ) -> Result<Self> {
     let data_dir = data_dir.as_ref();
     let file_path = data_dir.join("db.abb");
     let path = fs::canonicalize(&file_path).unwrap_or(file_path);
 
+    let lock = if writable {
+      // Create data directory, but don't create the database yet. The file
+      // will be created later by [`Database::save`] if any data is modified.
+      fs::create_dir_all(data_dir).with_context(|| {
+        format!("unable to create data directory: {}", data_dir.display())
+      })?;
+      Some(Self::lock(&path)?)
+    } else {
+      None
+    };
+
     match fs::read(&path) {
-      Ok(bytes) => Self::try_new(path, bytes, |bytes| Self::deserialize(bytes)),
+      Ok(bytes) => {
+        if external {
+          Self::verify(&path, &bytes)?;
+        }
+        let (version, _) = Self::split_version(&bytes)?;
+        let upgraded_from = (version != Self::VERSION).then_some(version);
+        Self::try_new(
+          path,
+          external,
+          upgraded_from,
+          false,
+          lock,
+          bytes,
+          |bytes| Self::deserialize(bytes),
+        )
+      }
       Err(e) if e.kind() == io::ErrorKind::NotFound => {
-        // Create data directory, but don't create any file yet. The file will be
-        // created later by [`Database::save`] if any data is modified.
-        fs::create_dir_all(data_dir).with_context(|| {
-          format!("unable to create data directory: {}", data_dir.display())
-        })?;
-        Ok(Self::new(path, Vec::new(), |_| Vec::new()))
+        Ok(Self::new(path, external, None, false, lock, Vec::new(), |_| {
+          Vec::new()
+        }))
       }
       Err(e) => Err(e).with_context(|| {
         format!("failed to read from database: {}", path.display())
@@ -148,50 +282,322 @@ impl Database {
     }
   }
 
+  /// Waits for other writers to finish, then locks the database until the
+  /// returned file is closed.
+  fn lock(path: &Path) -> Result<File> {
+    let lock_path = path.with_file_name("db.lock");
+    let file = OpenOptions::new()
+      .write(true)
+      .create(true)
+      .truncate(false)
+      .open(&lock_path)
+      .with_context(|| {
+        format!("failed to open lock file: {}", lock_path.display())
+      })?;
+    file.lock().with_context(|| {
+      format!("failed to lock database: {}", lock_path.display())
+    })?;
+    Ok(file)
+  }
+
+  /// Refuses an external database, unless it was allowed.
+  fn verify(path: &Path, bytes: &[u8]) -> Result<()> {
+    let allowed = Trust::open()?.check(path, bytes)?;
+    ensure!(
+      allowed,
+      "{} is denied, run `abbr allow {0}` to use it",
+      path.display()
+    );
+    Ok(())
+  }
+
+  /// Writes the database, keeping it trusted if it is external since the
+  /// change is the user's own.
+  fn write(&self, path: &Path, bytes: &[u8]) -> Result<()> {
+    util::write(path, bytes).context("failed to write database")?;
+    if *self.borrow_external() {
+      let mut trust = Trust::open()?;
+      trust.allow(path, bytes)?;
+      trust.save()?;
+    }
+    Ok(())
+  }
+
+  fn ensure_writable(&self) -> Result<()> {
+    ensure!(self.borrow_lock().is_some(), "database was opened read-only");
+    Ok(())
+  }
+
   fn swap_remove(&mut self, idx: usize) {
     self.with_records_mut(|elements| elements.swap_remove(idx));
   }
 }
 
+/// Read-only view of the database, mapped into memory so that a single record
+/// can be looked up without decoding the others.
+pub struct MappedDatabase {
+  bytes: Option<Bytes>,
+}
+
+enum Bytes {
+  /// Mapped from the user's own data directory.
+  Mapped(Mmap),
+  /// Read from an external database, which someone else may rewrite at any
+  /// time, so that the bytes decoded are the ones that were verified.
+  Read(Vec<u8>),
+}
+
+impl Deref for Bytes {
+  type Target = [u8];
+
+  fn deref(&self) -> &[u8] {
+    match self {
+      Bytes::Mapped(mmap) => mmap,
+      Bytes::Read(bytes) => bytes,
+    }
+  }
+}
+
+impl MappedDatabase {
+  /// Opens the database of the profile selected by `config`, which the caller
+  /// has loaded already.
+  pub fn open(config: &Config) -> Result<Self> {
+    let data_dir = config.profile_dir()?;
+    let external = trust::is_external(&data_dir);
+
+    Self::open_dir(data_dir, external)
+  }
+
+  fn open_dir(data_dir: impl AsRef<Path>, external: bool) -> Result<Self> {
+    let path = data_dir.as_ref().join("db.abb");
+    let read_error =
+      || format!("failed to read from database: {}", path.display());
+    if external {
+      let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+          return Ok(Self { bytes: None });
+        }
+        Err(e) => return Err(e).with_context(read_error),
+      };
+      Database::verify(&path, &bytes)?;
+      return Ok(Self { bytes: Some(Bytes::Read(bytes)) });
+    }
+
+    let file = match File::open(&path) {
+      Ok(file) => file,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => {
+        return Ok(Self { bytes: None });
+      }
+      Err(e) => return Err(e).with_context(read_error),
+    };
+    // SAFETY: only abbr writes to the user's own data directory, and
+    // [`util::write`] replaces the database with a new file instead of
+    // modifying it, so the mapped one stays unchanged. External databases may
+    // be modified in place by someone else, which is why they are read above.
+    let mmap = unsafe { Mmap::map(&file) }.with_context(read_error)?;
+    Ok(Self { bytes: Some(Bytes::Mapped(mmap)) })
+  }
+
+  pub fn records(&self) -> Result<Vec<Record<'_>>> {
+    match self.bytes.as_deref() {
+      Some(bytes) => Database::deserialize(bytes),
+      None => Ok(Vec::new()),
+    }
+  }
+
+  pub fn get(&self, alias: &str) -> Result<Option<Record<'_>>> {
+    let Some(bytes) = self.bytes.as_deref() else {
+      return Ok(None);
+    };
+
+    let (version, bytes_elements) = Database::split_version(bytes)?;
+    if version == Database::VERSION {
+      layout::find(bytes_elements, alias, Database::options())
+    } else {
+      // Older versions have to be read whole, until a writer upgrades them.
+      let records = Database::deserialize(bytes)?;
+      Ok(records.into_iter().find(|record| record.alias == alias))
+    }
+  }
+}
+
+/// A previous state of the database, kept by [`Database::save`].
+pub struct Snapshot {
+  pub path: PathBuf,
+  pub time: SystemTime,
+}
+
+impl Snapshot {
+  pub fn read(&self) -> Result<Vec<u8>> {
+    fs::read(&self.path).with_context(|| {
+      format!("failed to read snapshot: {}", self.path.display())
+    })
+  }
+
+  pub fn records(bytes: &[u8]) -> Result<Vec<Record<'_>>> {
+    Database::deserialize(bytes)
+  }
+}
+
 impl Database {
-  const VERSION: u32 = 8;
+  /// Number of previous states kept in the history.
+  const HISTORY_SIZE: usize = 10;
 
-  fn serialize(records: &[Record]) -> Result<Vec<u8>> {
-    (|| -> bincode::Result<_> {
-      // Preallocate buffer with combined size of sections.
-      let buffer_size = bincode::serialized_size(&Self::VERSION)?
-        + bincode::serialized_size(&records)?;
-      let mut buffer = Vec::with_capacity(buffer_size as usize);
+  fn history_dir(&self) -> PathBuf {
+    self.borrow_path().with_file_name("history")
+  }
+
+  /// File naming the snapshot the database was last restored to.
+  fn position_path(&self) -> PathBuf {
+    self.history_dir().join("position")
+  }
+
+  /// Saves the database as it was read into the history, unless the newest
+  /// snapshot is the same, removing the oldest ones beyond
+  /// [`Database::HISTORY_SIZE`].
+  fn snapshot(&self) -> Result<()> {
+    let dir = self.history_dir();
+    fs::create_dir_all(&dir).with_context(|| {
+      format!("unable to create history directory: {}", dir.display())
+    })?;
+    if let Some(newest) = self.history()?.first() {
+      if newest.read()? == *self.borrow_bytes() {
+        return Ok(());
+      }
+    }
+
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+    // Trusted like the database itself, so that it can be restored.
+    self.write(&dir.join(format!("{nanos}.abb")), self.borrow_bytes())?;
+
+    for snapshot in self.history()?.iter().skip(Self::HISTORY_SIZE) {
+      _ = fs::remove_file(&snapshot.path);
+    }
+    Ok(())
+  }
+
+  /// Lists the previous states of the database, newest first.
+  pub fn history(&self) -> Result<Vec<Snapshot>> {
+    let dir = self.history_dir();
+    let entries = match fs::read_dir(&dir) {
+      Ok(entries) => entries,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+      Err(e) => {
+        return Err(e).with_context(|| {
+          format!("failed to read history directory: {}", dir.display())
+        })
+      }
+    };
+
+    let mut snapshots = Vec::new();
+    for entry in entries {
+      let path = entry?.path();
+      // Skip anything but snapshots, such as leftover junk files.
+      let Some(nanos) = path
+        .file_name()
+        .and_then(|name| name.to_str()?.strip_suffix(".abb"))
+        .and_then(|nanos| nanos.parse::<u64>().ok())
+      else {
+        continue;
+      };
+      let time = UNIX_EPOCH + Duration::from_nanos(nanos);
+      snapshots.push(Snapshot { path, time });
+    }
+    snapshots.sort_unstable_by_key(|snapshot| Reverse(snapshot.time));
 
-      // Serialize sections into buffer.
-      bincode::serialize_into(&mut buffer, &Self::VERSION)?;
-      bincode::serialize_into(&mut buffer, &records)?;
+    Ok(snapshots)
+  }
+
+  /// Finds the index in `history` of the snapshot the database was last
+  /// restored to, as long as it wasn't changed since.
+  pub fn position(&self, history: &[Snapshot]) -> Result<Option<usize>> {
+    let path = self.position_path();
+    let name = match fs::read_to_string(&path) {
+      Ok(name) => name,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+      Err(e) => {
+        return Err(e).with_context(|| {
+          format!("failed to read history position: {}", path.display())
+        })
+      }
+    };
+    let Some(idx) = history
+      .iter()
+      .position(|snapshot| snapshot.path.file_name() == Some(name.as_ref()))
+    else {
+      return Ok(None);
+    };
+    let unchanged = history[idx].read()? == *self.borrow_bytes();
+    Ok(unchanged.then_some(idx))
+  }
+
+  /// Replaces the database file with `snapshot`, keeping the current state in
+  /// the history unless it is there already, so that it can be restored again.
+  pub fn restore(&self, snapshot: &Snapshot) -> Result<()> {
+    self.ensure_writable()?;
+    let bytes = snapshot.read()?;
+    // Restoring a snapshot trusts it, so it must be one written by the user.
+    if *self.borrow_external() {
+      Self::verify(&snapshot.path, &bytes)?;
+    }
+    // Make sure the snapshot is readable before replacing the database.
+    Self::deserialize(&bytes)?;
+    let restored = self.position(&self.history()?)?.is_some();
+    if !self.borrow_bytes().is_empty() && !restored {
+      self.snapshot().context("failed to save database history")?;
+    }
+    self.write(self.borrow_path(), &bytes)?;
+
+    let name = snapshot.path.file_name().unwrap_or_default();
+    util::write(self.position_path(), name.as_encoded_bytes())
+      .context("failed to save history position")
+  }
+}
 
+impl Database {
+  const VERSION: u32 = 11;
+
+  fn serialize(records: &[Record]) -> Result<Vec<u8>> {
+    (|| -> Result<_> {
+      let mut buffer = Self::options().serialize(&Self::VERSION)?;
+      buffer.append(&mut layout::serialize(records, Self::options())?);
       Ok(buffer)
     })()
     .context("failed to serialize database")
   }
 
-  fn deserialize(bytes: &[u8]) -> Result<Vec<Record>> {
-    // Assume a maximum size for the database. This prevents bincode from throwing
-    // strange errors when it encounters invalid data.
+  /// Assume a maximum size for the database. This prevents bincode from
+  /// throwing strange errors when it encounters invalid data.
+  fn options() -> impl Options + Copy {
     const MAX_SIZE: u64 = 32 << 20; // 32 MiB
-    let deserializer =
-      &mut bincode::options().with_fixint_encoding().with_limit(MAX_SIZE);
+    bincode::options().with_fixint_encoding().with_limit(MAX_SIZE)
+  }
 
-    // Split bytes into sections.
+  /// Splits bytes into the version and records sections.
+  fn split_version(bytes: &[u8]) -> Result<(u32, &[u8])> {
+    let deserializer = Self::options();
     let version_size = deserializer.serialized_size(&Self::VERSION)? as _;
     if bytes.len() < version_size {
       bail!("failed to deserialize database - data corrupted");
     }
     let (bytes_version, bytes_elements) = bytes.split_at(version_size);
-
-    // Deserialize sections.
     let version = deserializer.deserialize(bytes_version)?;
+    Ok((version, bytes_elements))
+  }
+
+  fn deserialize(bytes: &[u8]) -> Result<Vec<Record<'_>>> {
+    let deserializer = Self::options();
+    let (version, bytes_elements) = Self::split_version(bytes)?;
+
     let elements = match version {
-      Self::VERSION => deserializer
-        .deserialize(bytes_elements)
-        .context("failed to deserialize database")?,
+      Self::VERSION => layout::deserialize(bytes_elements, deserializer)?,
+      version
+        if (migration::OLDEST_VERSION..Self::VERSION).contains(&version) =>
+      {
+        migration::upgrade(version, bytes_elements, deserializer)
+          .context("failed to upgrade database")?
+      }
       version => {
         bail!("unsupported version (got {version}, supports {})", Self::VERSION)
       }
@@ -216,36 +622,41 @@ pub mod test {
     where
       Self: Sized,
     {
-      let mut records = Vec::new();
-
-      records.push(Record {
-        origin: "echo".into(),
-        alias: "e".into(),
-        mappings: Vec::new(),
-      });
-
-      records.push(Record {
-        origin: "git tag -d".into(),
-        alias: "gtd".into(),
-        mappings: Vec::new(),
-      });
-
-      records.push(Record {
-        origin: "docker".into(),
-        alias: "dk".into(),
-        mappings: vec![
-          ("cmp".into(), "compose".into()),
-          ("ud".into(), "up -d".into()),
-          ("i".into(), "image".into()),
-          ("l".into(), "ls".into()),
-        ],
-      });
-
-      records.push(Record {
-        origin: "cargo".into(),
-        alias: "ca".into(),
-        mappings: vec![("a".into(), "add".into())],
-      });
+      let records = vec![
+        Record {
+          origin: "echo".into(),
+          alias: "e".into(),
+          mappings: Vec::new(),
+          defaults: Vec::new(),
+          combined_flags: false,
+        },
+        Record {
+          origin: "git tag -d".into(),
+          alias: "gtd".into(),
+          mappings: Vec::new(),
+          defaults: Vec::new(),
+          combined_flags: false,
+        },
+        Record {
+          origin: "docker".into(),
+          alias: "dk".into(),
+          mappings: vec![
+            ("cmp".into(), "compose".into()),
+            ("ud".into(), "up -d".into()),
+            ("i".into(), "image".into()),
+            ("l".into(), "ls".into()),
+          ],
+          defaults: Vec::new(),
+          combined_flags: false,
+        },
+        Record {
+          origin: "cargo".into(),
+          alias: "ca".into(),
+          mappings: vec![("a".into(), "add".into())],
+          defaults: Vec::new(),
+          combined_flags: false,
+        },
+      ];
 
       Ok(Self { records })
     }
@@ -294,6 +705,29 @@ pub mod test {
       }
     }
 
+    fn set_defaults<S, D>(&mut self, alias: S, defaults: D)
+    where
+      S: AsRef<str> + Into<String>,
+      D: Iterator<Item = S>,
+    {
+      if let Some(record) =
+        self.records.iter_mut().find(|r| r.alias == alias.as_ref())
+      {
+        record.defaults = defaults.map(|d| d.into().into()).collect();
+      }
+    }
+
+    fn set_combined_flags<S>(&mut self, alias: S, enabled: bool)
+    where
+      S: AsRef<str>,
+    {
+      if let Some(record) =
+        self.records.iter_mut().find(|r| r.alias == alias.as_ref())
+      {
+        record.combined_flags = enabled;
+      }
+    }
+
     fn del_record<S>(&mut self, alias: S)
     where
       S: AsRef<str>,
@@ -301,8 +735,223 @@ pub mod test {
       self.records.retain(|record| record.alias != alias.as_ref());
     }
 
-    fn records(&self) -> &[Record] {
+    fn records(&self) -> &[Record<'_>] {
       self.records.as_slice()
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use crate::database::{
+    Database, DbOperation, MappedDatabase, Record, Snapshot,
+  };
+  use std::fs;
+  use std::path::Path;
+
+  /// Databases written by each version, keyed by the version.
+  const FIXTURES: &[(u32, &[u8])] = &[
+    (8, include_bytes!("database/fixtures/v8.abb")),
+    (9, include_bytes!("database/fixtures/v9.abb")),
+    (10, include_bytes!("database/fixtures/v10.abb")),
+    (11, include_bytes!("database/fixtures/v11.abb")),
+  ];
+
+  type Flat<'a> = (&'a str, &'a str, Vec<(&'a str, &'a str)>, Vec<&'a str>);
+
+  fn flatten<'a>(records: &'a [Record]) -> Vec<Flat<'a>> {
+    records
+      .iter()
+      .map(|r| {
+        (
+          r.alias.as_ref(),
+          r.origin.as_ref(),
+          r.mappings.iter().map(|(a, o)| (a.as_ref(), o.as_ref())).collect(),
+          r.defaults.iter().map(|d| d.as_ref()).collect(),
+        )
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_fixture_versions() {
+    // Every version from the oldest supported one must have a fixture.
+    let versions = FIXTURES.iter().map(|(v, _)| *v).collect::<Vec<_>>();
+    assert_eq!(
+      versions,
+      (super::migration::OLDEST_VERSION..=Database::VERSION)
+        .collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn test_deserialize_fixtures() {
+    for (version, bytes) in FIXTURES {
+      let records = Database::deserialize(bytes).unwrap();
+      let defaults =
+        if *version >= 9 { vec!["hello", "world"] } else { vec![] };
+
+      assert_eq!(
+        flatten(&records),
+        vec![
+          ("dk", "docker", vec![("cmp", "compose")], vec![]),
+          ("e", "echo", vec![], defaults),
+        ],
+        "version {version}"
+      );
+      assert_eq!(records[0].combined_flags, *version >= 11);
+    }
+  }
+
+  #[test]
+  fn test_unsupported_version() {
+    let mut bytes = 7u32.to_le_bytes().to_vec();
+    bytes.extend_from_slice(&0u64.to_le_bytes());
+
+    let err = Database::deserialize(&bytes).unwrap_err();
+    assert!(err.to_string().starts_with("unsupported version (got 7"));
+  }
+
+  #[test]
+  fn test_upgrade_backup() {
+    let (_, v8) = FIXTURES[0];
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.abb");
+    fs::write(&path, v8).unwrap();
+
+    let mut db = Database::open_dir(dir.path(), true, false).unwrap();
+    assert_eq!(db.records().len(), 2);
+    assert!(!dir.path().join("db.abb.v8.bak").exists());
+
+    db.save().unwrap();
+    assert_eq!(fs::read(dir.path().join("db.abb.v8.bak")).unwrap(), v8);
+    let bytes = fs::read(&path).unwrap();
+    let (version, _) = Database::split_version(&bytes).unwrap();
+    assert_eq!(version, Database::VERSION);
+
+    // The backup is only written once.
+    fs::remove_file(dir.path().join("db.abb.v8.bak")).unwrap();
+    db.save().unwrap();
+    assert!(!dir.path().join("db.abb.v8.bak").exists());
+  }
+
+  fn add_alias(dir: &Path, alias: &str) {
+    let mut db = Database::open_dir(dir, true, false).unwrap();
+    db.add_record(Record {
+      origin: "echo".into(),
+      alias: alias.to_owned().into(),
+      mappings: Vec::new(),
+      defaults: Vec::new(),
+      combined_flags: false,
+    });
+    db.save().unwrap();
+  }
+
+  fn aliases(dir: &Path) -> Vec<String> {
+    let db = Database::open_dir(dir, false, false).unwrap();
+    let mut aliases =
+      db.records().iter().map(|r| r.alias.to_string()).collect::<Vec<_>>();
+    aliases.sort_unstable();
+    aliases
+  }
+
+  #[test]
+  fn test_history_restore() {
+    let dir = tempfile::tempdir().unwrap();
+    for alias in ["a", "b", "c"] {
+      add_alias(dir.path(), alias);
+    }
+
+    // The first save had nothing to keep.
+    let db = Database::open_dir(dir.path(), true, false).unwrap();
+    let history = db.history().unwrap();
+    assert_eq!(history.len(), 2);
+    let states = history
+      .iter()
+      .map(|snapshot| {
+        let bytes = snapshot.read().unwrap();
+        Snapshot::records(&bytes).unwrap().len()
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(states, vec![2, 1]);
+
+    db.restore(&history[1]).unwrap();
+    assert_eq!(aliases(dir.path()), vec!["a"]);
+    // The state before restoring is kept, along with the newer snapshots.
+    let history = db.history().unwrap();
+    assert_eq!(history.len(), 3);
+
+    drop(db);
+    let db = Database::open_dir(dir.path(), true, false).unwrap();
+    assert_eq!(db.position(&history).unwrap(), Some(2));
+    db.restore(&history[0]).unwrap();
+    assert_eq!(aliases(dir.path()), vec!["a", "b", "c"]);
+    // The restored state was in the history already.
+    assert_eq!(db.history().unwrap().len(), 3);
+  }
+
+  #[test]
+  fn test_history_position() {
+    let dir = tempfile::tempdir().unwrap();
+    for alias in ["a", "b"] {
+      add_alias(dir.path(), alias);
+    }
+
+    let db = Database::open_dir(dir.path(), true, false).unwrap();
+    assert_eq!(db.position(&db.history().unwrap()).unwrap(), None);
+    db.restore(&db.history().unwrap()[0]).unwrap();
+    drop(db);
+
+    let db = Database::open_dir(dir.path(), true, false).unwrap();
+    let history = db.history().unwrap();
+    assert_eq!(db.position(&history).unwrap(), Some(1));
+    drop(db);
+
+    // Saving moves on from the restored state.
+    add_alias(dir.path(), "c");
+    let db = Database::open_dir(dir.path(), true, false).unwrap();
+    let history = db.history().unwrap();
+    assert_eq!(db.position(&history).unwrap(), None);
+    assert_eq!(history.len(), 3);
+  }
+
+  #[test]
+  fn test_mapped_get() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db.abb");
+
+    let db = MappedDatabase::open_dir(dir.path(), false).unwrap();
+    assert!(db.get("e").unwrap().is_none());
+
+    for (version, bytes) in FIXTURES {
+      fs::write(&path, bytes).unwrap();
+      let db = MappedDatabase::open_dir(dir.path(), false).unwrap();
+      let record = db.get("dk").unwrap().unwrap();
+      assert_eq!(record.origin, "docker", "version {version}");
+      assert!(db.get("d").unwrap().is_none(), "version {version}");
+    }
+  }
+
+  #[test]
+  fn test_readonly_save() {
+    let dir = tempfile::tempdir().unwrap();
+    add_alias(dir.path(), "a");
+
+    let mut db = Database::open_dir(dir.path(), false, false).unwrap();
+    assert_eq!(
+      db.save().unwrap_err().to_string(),
+      "database was opened read-only"
+    );
+  }
+
+  #[test]
+  fn test_history_size() {
+    let dir = tempfile::tempdir().unwrap();
+    for idx in 0..Database::HISTORY_SIZE + 3 {
+      add_alias(dir.path(), &idx.to_string());
+    }
+
+    let db = Database::open_dir(dir.path(), false, false).unwrap();
+    assert_eq!(db.history().unwrap().len(), Database::HISTORY_SIZE);
+  }
+}
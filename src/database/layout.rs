@@ -0,0 +1,174 @@
+//! Layout of the records section, indexed so that a single record can be
+//! looked up without decoding the others:
+//!
+//! ```text
+//! count:   u32
+//! offsets: [u32; count + 1]  bounds of each record within `records`
+//! records: [Record; count]   sorted by alias
+//! ```
+//!
+//! Integers are little-endian, records are encoded with bincode.
+
+use crate::database::Record;
+use anyhow::{bail, Context, Result};
+use bincode::Options;
+use serde::Deserialize;
+
+const WORD: usize = size_of::<u32>();
+
+pub fn serialize(
+  records: &[Record],
+  options: impl Options + Copy,
+) -> Result<Vec<u8>> {
+  let mut sorted = records.iter().collect::<Vec<_>>();
+  sorted.sort_unstable_by(|a, b| a.alias.cmp(&b.alias));
+
+  let table_size = WORD * (sorted.len() + 2);
+  let mut offsets = Vec::with_capacity(table_size);
+  let mut body = Vec::new();
+  offsets.extend(to_word(sorted.len())?);
+  offsets.extend(to_word(0)?);
+  for record in sorted {
+    options.serialize_into(&mut body, record)?;
+    offsets.extend(to_word(body.len())?);
+  }
+
+  offsets.append(&mut body);
+  Ok(offsets)
+}
+
+pub fn deserialize<'b, R: Deserialize<'b>>(
+  bytes: &'b [u8],
+  options: impl Options + Copy,
+) -> Result<Vec<R>> {
+  let table = Table::parse(bytes)?;
+  (0..table.len).map(|idx| table.record(idx, options)).collect()
+}
+
+/// Binary searches the records for `alias`, decoding only the ones probed.
+pub fn find<'b>(
+  bytes: &'b [u8],
+  alias: &str,
+  options: impl Options + Copy,
+) -> Result<Option<Record<'b>>> {
+  let table = Table::parse(bytes)?;
+  let (mut low, mut high) = (0, table.len);
+  while low < high {
+    let mid = low + (high - low) / 2;
+    // A record starts with its origin, followed by its alias.
+    let (_, key) = options
+      .allow_trailing_bytes()
+      .deserialize::<(&str, &str)>(table.slice(mid)?)
+      .context("failed to deserialize database")?;
+    match key.cmp(alias) {
+      std::cmp::Ordering::Less => low = mid + 1,
+      std::cmp::Ordering::Greater => high = mid,
+      std::cmp::Ordering::Equal => return table.record(mid, options).map(Some),
+    }
+  }
+
+  Ok(None)
+}
+
+struct Table<'b> {
+  len: usize,
+  offsets: &'b [u8],
+  records: &'b [u8],
+}
+
+impl<'b> Table<'b> {
+  fn parse(bytes: &'b [u8]) -> Result<Self> {
+    let len = match bytes.get(..WORD) {
+      Some(word) => from_word(word),
+      None => bail!("failed to deserialize database - data corrupted"),
+    };
+    let table_size = len
+      .checked_add(1)
+      .and_then(|words| words.checked_mul(WORD))
+      .filter(|size| *size <= bytes.len() - WORD)
+      .context("failed to deserialize database - data corrupted")?;
+    let (offsets, records) = bytes[WORD..].split_at(table_size);
+
+    Ok(Self { len, offsets, records })
+  }
+
+  fn slice(&self, idx: usize) -> Result<&'b [u8]> {
+    let offset = |idx: usize| from_word(&self.offsets[idx * WORD..][..WORD]);
+    self
+      .records
+      .get(offset(idx)..offset(idx + 1))
+      .context("failed to deserialize database - data corrupted")
+  }
+
+  fn record<R: Deserialize<'b>>(
+    &self,
+    idx: usize,
+    options: impl Options,
+  ) -> Result<R> {
+    options
+      .deserialize(self.slice(idx)?)
+      .context("failed to deserialize database")
+  }
+}
+
+fn to_word(value: usize) -> Result<[u8; WORD]> {
+  let value = u32::try_from(value).context("database is too large")?;
+  Ok(value.to_le_bytes())
+}
+
+fn from_word(bytes: &[u8]) -> usize {
+  u32::from_le_bytes(bytes.try_into().unwrap()) as usize
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::database::layout::{deserialize, find, serialize};
+  use crate::database::{Database, Record};
+
+  fn records(count: usize) -> Vec<Record<'static>> {
+    (0..count)
+      .rev()
+      .map(|idx| Record {
+        origin: format!("echo {idx}").into(),
+        alias: format!("e{idx}").into(),
+        mappings: vec![("a".into(), "b".into())],
+        defaults: Vec::new(),
+        combined_flags: false,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_find() {
+    for count in [0, 1, 2, 100] {
+      let bytes = serialize(&records(count), Database::options()).unwrap();
+      assert_eq!(
+        deserialize::<Record>(&bytes, Database::options()).unwrap().len(),
+        count
+      );
+
+      for idx in 0..count {
+        let alias = format!("e{idx}");
+        let record = find(&bytes, &alias, Database::options()).unwrap();
+        assert_eq!(record.unwrap().origin, format!("echo {idx}"));
+      }
+      for alias in ["", "e", "e1x", "f"] {
+        let record = find(&bytes, alias, Database::options()).unwrap();
+        assert!(record.is_none(), "{alias} in {count} records");
+      }
+    }
+  }
+
+  #[test]
+  fn test_corrupted() {
+    let bytes = serialize(&records(10), Database::options()).unwrap();
+
+    assert!(find(&bytes[..2], "e1", Database::options()).is_err());
+    assert!(find(&bytes[..40], "e1", Database::options()).is_err());
+    assert!(deserialize::<Record>(
+      &bytes[..bytes.len() - 1],
+      Database::options()
+    )
+    .is_err());
+  }
+}
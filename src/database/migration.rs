@@ -0,0 +1,161 @@
+//! Layouts of [`Record`] in older versions of the database.
+//!
+//! Each layout converts into the one of the next version, so a database of any
+//! supported version is upgraded step by step up to [`Database::VERSION`].
+//! When changing [`Record`], freeze its current layout here as
+//! `RecordV<VERSION>`, bump the version and add a step to [`upgrade`].
+//!
+//! [`Database::VERSION`]: crate::database::Database
+
+use crate::database::{layout, Record, Str};
+use crate::util::SCOPE_DELIMITER;
+use anyhow::{bail, Context, Result};
+use bincode::Options;
+use serde::Deserialize;
+
+/// Oldest version that can still be upgraded.
+pub const OLDEST_VERSION: u32 = 8;
+
+/// Version 8, before defaults and placeholders were added.
+#[derive(Deserialize)]
+struct RecordV8<'r> {
+  origin: Str<'r>,
+  alias: Str<'r>,
+  mappings: Vec<(Str<'r>, Str<'r>)>,
+}
+
+impl<'r> From<RecordV8<'r>> for RecordV10<'r> {
+  fn from(record: RecordV8<'r>) -> Self {
+    // Braces were literal, such as in `HEAD@{1}`, before they could start a
+    // placeholder.
+    let origin = if record.origin.contains(['{', '}']) {
+      record.origin.replace('{', "{{").replace('}', "}}").into()
+    } else {
+      record.origin
+    };
+    Self {
+      origin,
+      alias: record.alias,
+      mappings: record.mappings,
+      defaults: Vec::new(),
+    }
+  }
+}
+
+/// Version 9 stored the records as a single list, before they were indexed
+/// by alias. The records themselves are unchanged.
+type RecordsV9<'r> = Vec<RecordV10<'r>>;
+
+/// Version 10, before combined flags and scoped parameters were added.
+#[derive(Deserialize)]
+struct RecordV10<'r> {
+  origin: Str<'r>,
+  alias: Str<'r>,
+  mappings: Vec<(Str<'r>, Str<'r>)>,
+  defaults: Vec<Str<'r>>,
+}
+
+impl<'r> From<RecordV10<'r>> for Record<'r> {
+  fn from(record: RecordV10<'r>) -> Self {
+    // Dots were literal, such as in `Dockerfile.dev`, before they could scope
+    // a parameter.
+    let mappings = record
+      .mappings
+      .into_iter()
+      .map(|(alias, origin)| {
+        if alias.contains(SCOPE_DELIMITER) {
+          (alias.replace(SCOPE_DELIMITER, "\\.").into(), origin)
+        } else {
+          (alias, origin)
+        }
+      })
+      .collect();
+    Self {
+      origin: record.origin,
+      alias: record.alias,
+      mappings,
+      defaults: record.defaults,
+      combined_flags: false,
+    }
+  }
+}
+
+/// Reads records stored in an older `version` and upgrades them to the
+/// current layout.
+pub fn upgrade<'b>(
+  version: u32,
+  bytes: &'b [u8],
+  deserializer: impl Options + Copy,
+) -> Result<Vec<Record<'b>>> {
+  let records = match version {
+    8 => deserializer
+      .deserialize::<Vec<RecordV8>>(bytes)
+      .with_context(|| format!("failed to deserialize version {version}"))?
+      .into_iter()
+      .map(RecordV10::from)
+      .collect(),
+    9 => deserializer
+      .deserialize::<RecordsV9>(bytes)
+      .with_context(|| format!("failed to deserialize version {version}"))?,
+    10 => layout::deserialize::<RecordV10>(bytes, deserializer)
+      .with_context(|| format!("failed to deserialize version {version}"))?,
+    version => bail!("no upgrade from version {version}"),
+  };
+
+  Ok(records.into_iter().map(Record::from).collect())
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::command::query::{self, Part};
+  use crate::database::migration::upgrade;
+  use crate::database::Database;
+  use crate::util;
+  use bincode::Options;
+
+  #[test]
+  fn test_upgrade_braces() {
+    let origins = ["git log @{u}..HEAD", "git show HEAD@{1}", "echo {}"];
+    let records = origins
+      .iter()
+      .enumerate()
+      .map(|(idx, origin)| {
+        (*origin, format!("a{idx}"), Vec::<(&str, &str)>::new())
+      })
+      .collect::<Vec<_>>();
+    let bytes = Database::options().serialize(&records).unwrap();
+
+    let records = upgrade(8, &bytes, Database::options()).unwrap();
+    assert_eq!(records[0].origin, "git log @{{u}}..HEAD");
+    for (record, origin) in records.iter().zip(origins) {
+      // The command expands the same as before, without placeholders.
+      let text = query::parse_template(&record.origin)
+        .into_iter()
+        .map(|part| match part {
+          Part::Text(text) => text,
+          part => panic!("unexpected placeholder: {part:?}"),
+        })
+        .collect::<String>();
+      assert_eq!(text, origin);
+    }
+  }
+
+  #[test]
+  fn test_upgrade_dots() {
+    let records = vec![(
+      "docker",
+      "dk",
+      vec![("df.dev", "Dockerfile.dev"), ("b", "build")],
+      Vec::<&str>::new(),
+    )];
+    let bytes = Database::options().serialize(&records).unwrap();
+
+    let records = upgrade(9, &bytes, Database::options()).unwrap();
+    let (alias, origin) = &records[0].mappings[0];
+    assert_eq!(alias, "df\\.dev");
+    assert_eq!(origin, "Dockerfile.dev");
+    assert_eq!(util::param_to_scope(alias), (None, "df\\.dev"));
+    assert_eq!(util::unescape_scope(alias), "df.dev");
+    assert_eq!(records[0].mappings[1].0, "b");
+  }
+}
@@ -2,7 +2,9 @@ mod command;
 mod config;
 mod database;
 mod error;
+mod project;
 mod shell;
+mod trust;
 mod util;
 
 use crate::command::{Command, Execute};
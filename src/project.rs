@@ -0,0 +1,211 @@
+use crate::command::export;
+use crate::database::Record;
+use crate::trust::Trust;
+use anyhow::{Context, Result};
+use std::env;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str;
+
+/// Name of the per-project alias file, in the same format as
+/// `abbr export --format toml`.
+pub const FILE_NAME: &str = ".abbr.toml";
+
+/// Aliases of the project containing the current directory, which take
+/// precedence over the ones in the database.
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Project {
+  pub path: PathBuf,
+  pub records: Vec<Record<'static>>,
+}
+
+/// Where a record was defined.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Layer<'p> {
+  Project(&'p Path),
+  Global,
+}
+
+impl Display for Layer<'_> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Layer::Project(path) => write!(f, "{}", path.display()),
+      Layer::Global => write!(f, "global"),
+    }
+  }
+}
+
+impl Project {
+  /// Finds the closest project file, walking up from the current directory.
+  /// Skips it if it was denied, and warns about it if it isn't trusted or
+  /// can't be parsed, so that the global aliases stay usable.
+  pub fn find() -> Option<Self> {
+    let path = Self::locate()?;
+    match Trust::open().and_then(|trust| Self::load(path, &trust)) {
+      Ok(project) => project,
+      Err(e) => {
+        _ = writeln!(io::stderr(), "abbr: {e:?}");
+        None
+      }
+    }
+  }
+
+  pub fn locate() -> Option<PathBuf> {
+    // The current directory may have been removed.
+    let dir = env::current_dir().ok()?;
+    Self::locate_from(&dir)
+  }
+
+  fn locate_from(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors().map(|dir| dir.join(FILE_NAME)).find(|path| path.is_file())
+  }
+
+  fn load(path: PathBuf, trust: &Trust) -> Result<Option<Self>> {
+    let contents = fs::read(&path)
+      .with_context(|| format!("failed to read file: {}", path.display()))?;
+    if !trust.check(&path, &contents)? {
+      return Ok(None);
+    }
+
+    let records = match str::from_utf8(&contents)
+      .map_err(anyhow::Error::from)
+      .and_then(export::from_toml)
+    {
+      Ok(records) => records,
+      Err(e) => {
+        // Keep the global aliases usable until the file is fixed.
+        let e = e.context(format!(
+          "invalid project file, ignoring it: {}",
+          path.display()
+        ));
+        _ = writeln!(io::stderr(), "abbr: {e:?}");
+        return Ok(None);
+      }
+    };
+    Ok(Some(Self { path, records }))
+  }
+
+  #[cfg(test)]
+  pub fn find_from(dir: &Path, trust: &Trust) -> Result<Option<Self>> {
+    match Self::locate_from(dir) {
+      Some(path) => Self::load(path, trust),
+      None => Ok(None),
+    }
+  }
+
+  pub fn get(&self, alias: &str) -> Option<&Record<'static>> {
+    self.records.iter().find(|record| record.alias == alias)
+  }
+}
+
+/// Layers the records of `project` over `global`, dropping the global records
+/// they shadow.
+pub fn layer<'a>(
+  project: Option<&'a Project>,
+  global: &'a [Record<'a>],
+) -> Vec<(Layer<'a>, &'a Record<'a>)> {
+  let mut records = Vec::with_capacity(global.len());
+  if let Some(project) = project {
+    let layer = Layer::Project(&project.path);
+    records.extend(project.records.iter().map(|record| (layer, record)));
+  }
+  for record in global {
+    if project.is_none_or(|project| project.get(&record.alias).is_none()) {
+      records.push((Layer::Global, record));
+    }
+  }
+  records
+}
+
+#[cfg(test)]
+pub mod test {
+  use crate::project::FILE_NAME;
+  use crate::trust::Trust;
+  use std::fs;
+  use std::path::Path;
+
+  /// Writes a project file into `dir` and allows it.
+  pub fn write_project(dir: &Path, contents: &str) -> Trust {
+    let path = dir.join(FILE_NAME);
+    fs::write(&path, contents).unwrap();
+    let mut trust = Trust::open_dir(dir).unwrap();
+    trust.allow(&path, contents.as_bytes()).unwrap();
+    trust
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::database::{test::DummyDatabase, DbOperation};
+  use crate::project::{layer, test::write_project, Layer, Project, FILE_NAME};
+  use crate::trust::Trust;
+  use std::fs;
+
+  const PROJECT: &str = r#"
+[[records]]
+alias = "e"
+origin = "echo project"
+
+[[records]]
+alias = "t"
+origin = "make -C services/api test"
+"#;
+
+  #[test]
+  fn test_find_from() {
+    let dir = tempfile::tempdir().unwrap();
+    let nested = dir.path().join("services/api");
+    fs::create_dir_all(&nested).unwrap();
+    let trust = Trust::open_dir(dir.path()).unwrap();
+    assert!(Project::find_from(&nested, &trust).unwrap().is_none());
+
+    let trust = write_project(dir.path(), PROJECT);
+    let project = Project::find_from(&nested, &trust).unwrap().unwrap();
+    assert_eq!(project.path, dir.path().join(FILE_NAME));
+    assert_eq!(project.get("t").unwrap().origin, "make -C services/api test");
+
+    let trust = write_project(dir.path(), "records = 1");
+    assert!(Project::find_from(&nested, &trust).unwrap().is_none());
+  }
+
+  #[test]
+  fn test_find_untrusted() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(FILE_NAME);
+    let mut trust = write_project(dir.path(), PROJECT);
+
+    fs::write(&path, "").unwrap();
+    let err = Project::find_from(dir.path(), &trust).unwrap_err();
+    assert!(err.to_string().contains("changed since it was allowed"));
+
+    trust.deny(&path).unwrap();
+    assert!(Project::find_from(dir.path(), &trust).unwrap().is_none());
+  }
+
+  #[test]
+  fn test_layer() {
+    let dir = tempfile::tempdir().unwrap();
+    let trust = write_project(dir.path(), PROJECT);
+    let project = Project::find_from(dir.path(), &trust).unwrap();
+    let db = DummyDatabase::open().unwrap();
+
+    let records = layer(project.as_ref(), db.records())
+      .into_iter()
+      .map(|(layer, record)| (layer, record.origin.as_ref()))
+      .collect::<Vec<_>>();
+    let path = dir.path().join(FILE_NAME);
+    assert_eq!(
+      records,
+      vec![
+        (Layer::Project(&path), "echo project"),
+        (Layer::Project(&path), "make -C services/api test"),
+        (Layer::Global, "git tag -d"),
+        (Layer::Global, "docker"),
+        (Layer::Global, "cargo"),
+      ]
+    );
+  }
+}
@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct Opts<'a> {
   pub cmd: Option<&'a str>,
@@ -20,4 +22,152 @@ macro_rules! make_template {
 }
 
 make_template!(Bash, "bash");
+make_template!(Fish, "fish");
+make_template!(Nushell, "nushell");
+make_template!(Powershell, "powershell");
 make_template!(Zsh, "zsh");
+
+/// A stored alias as a standalone shell function, with its command and
+/// parameters already quoted for the target shell.
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Function<'a> {
+  pub name: &'a str,
+  /// Unquoted command, for comments.
+  pub command: &'a str,
+  /// Command split around its placeholders.
+  pub template: Vec<Piece<'a>>,
+  /// Names of the `{name}` placeholders, indexed by [`Piece::Name`].
+  pub names: Vec<&'a str>,
+  /// Arguments moved into `{N}` placeholders, counting from 1.
+  pub used: Vec<usize>,
+  pub mappings: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+  /// Defaults to append after N arguments, at index N.
+  pub defaults: Vec<Cow<'a, str>>,
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum Piece<'a> {
+  Text(Cow<'a, str>),
+  /// Filled by the N-th argument, counting from 1.
+  Index(usize),
+  /// Filled by a `name=value` argument.
+  Name(usize),
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(::rinja::Template)]
+#[template(path = "export/posix")]
+pub struct ExportPosix<'a> {
+  pub shell: &'a str,
+  pub functions: &'a [Function<'a>],
+  /// Aliases that can't be exported, along with the reason.
+  pub skipped: &'a [(&'a str, String)],
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(::rinja::Template)]
+#[template(path = "export/fish")]
+pub struct ExportFish<'a> {
+  pub functions: &'a [Function<'a>],
+  /// Aliases that can't be exported, along with the reason.
+  pub skipped: &'a [(&'a str, String)],
+}
+
+/// Quotes a word so that bash or zsh evaluates it back verbatim, in the
+/// style of `printf %q`.
+pub fn quote_posix(word: &str) -> Cow<'_, str> {
+  fn is_safe(c: char) -> bool {
+    c.is_alphanumeric()
+      || matches!(c, ',' | '.' | '_' | '+' | ':' | '@' | '%' | '/' | '-')
+  }
+
+  if word.is_empty() {
+    return "''".into();
+  }
+  if word.chars().all(is_safe) {
+    return word.into();
+  }
+
+  let mut quoted = String::with_capacity(word.len() * 2);
+  if word.chars().any(char::is_control) {
+    // Control characters can't be escaped with a backslash, so fall back to
+    // ANSI-C quoting.
+    quoted.push_str("$'");
+    for c in word.chars() {
+      match c {
+        '\\' | '\'' => {
+          quoted.push('\\');
+          quoted.push(c);
+        }
+        '\n' => quoted.push_str("\\n"),
+        '\t' => quoted.push_str("\\t"),
+        '\r' => quoted.push_str("\\r"),
+        c if c.is_control() && (c as u32) < 0x80 => {
+          quoted.push_str(&format!("\\x{:02x}", c as u32))
+        }
+        c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+        c => quoted.push(c),
+      }
+    }
+    quoted.push('\'');
+  } else {
+    for c in word.chars() {
+      if !is_safe(c) {
+        quoted.push('\\');
+      }
+      quoted.push(c);
+    }
+  }
+
+  quoted.into()
+}
+
+/// Quotes a word so that fish evaluates it back verbatim.
+pub fn quote_fish(word: &str) -> Cow<'_, str> {
+  fn is_safe(c: char) -> bool {
+    c.is_alphanumeric()
+      || matches!(c, ',' | '.' | '_' | '+' | ':' | '@' | '/' | '-' | '=')
+  }
+
+  if !word.is_empty() && word.chars().all(is_safe) {
+    return word.into();
+  }
+
+  // Within single quotes fish only interprets `\\` and `\'`.
+  let mut quoted = String::with_capacity(word.len() + 2);
+  quoted.push('\'');
+  for c in word.chars() {
+    if matches!(c, '\\' | '\'') {
+      quoted.push('\\');
+    }
+    quoted.push(c);
+  }
+  quoted.push('\'');
+
+  quoted.into()
+}
+
+/// Quotes a word so that PowerShell evaluates it back verbatim.
+pub fn quote_powershell(word: &str) -> Cow<'_, str> {
+  fn is_safe(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '.' | '_' | '+' | ':' | '/' | '-' | '=')
+  }
+
+  if !word.is_empty() && word.chars().all(is_safe) {
+    return word.into();
+  }
+
+  // Within single quotes PowerShell only interprets a doubled quote, which
+  // includes the typographic single quotes.
+  let mut quoted = String::with_capacity(word.len() + 2);
+  quoted.push('\'');
+  for c in word.chars() {
+    if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}') {
+      quoted.push(c);
+    }
+    quoted.push(c);
+  }
+  quoted.push('\'');
+
+  quoted.into()
+}
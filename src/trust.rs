@@ -0,0 +1,172 @@
+//! Alias sources that someone else may have written, such as project files,
+//! imported files and databases outside the default data directory, have to
+//! be allowed by the user before their commands are evaluated. The
+//! hash of each allowed source is kept in the user's own data directory, so
+//! that a source can't vouch for itself, and any change to it has to be
+//! allowed again.
+
+use crate::{config, util};
+use anyhow::{bail, ensure, Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Eq)]
+enum Entry {
+  Allow(String),
+  Deny,
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Trust {
+  path: PathBuf,
+  entries: BTreeMap<PathBuf, Entry>,
+}
+
+impl Trust {
+  pub fn open() -> Result<Self> {
+    let data_dir =
+      config::home_data_dir().context("failed to find data directory")?;
+
+    Self::open_dir(data_dir)
+  }
+
+  pub fn open_dir(data_dir: impl AsRef<Path>) -> Result<Self> {
+    let path = data_dir.as_ref().join("trusted");
+    let contents = match fs::read_to_string(&path) {
+      Ok(contents) => contents,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+      Err(e) => {
+        return Err(e).with_context(|| {
+          format!("failed to read trusted sources: {}", path.display())
+        });
+      }
+    };
+
+    // Each line is `allow <hash> <path>` or `deny <path>`.
+    let mut entries = BTreeMap::new();
+    for line in contents.lines() {
+      let entry = match line.split_once(' ') {
+        Some(("allow", rest)) => rest
+          .split_once(' ')
+          .map(|(hash, source)| (source, Entry::Allow(hash.to_owned()))),
+        Some(("deny", source)) => Some((source, Entry::Deny)),
+        _ => None,
+      };
+      let Some((source, entry)) = entry else {
+        bail!("invalid line in trusted sources: {line}");
+      };
+      entries.insert(PathBuf::from(source), entry);
+    }
+
+    Ok(Self { path, entries })
+  }
+
+  pub fn save(&self) -> Result<()> {
+    let mut contents = String::new();
+    for (source, entry) in self.entries.iter() {
+      let source = source.display();
+      _ = match entry {
+        Entry::Allow(hash) => writeln!(contents, "allow {hash} {source}"),
+        Entry::Deny => writeln!(contents, "deny {source}"),
+      };
+    }
+
+    let dir = self.path.parent().unwrap();
+    fs::create_dir_all(dir).with_context(|| {
+      format!("unable to create data directory: {}", dir.display())
+    })?;
+    util::write(&self.path, contents).context("failed to write trusted sources")
+  }
+
+  pub fn allow(&mut self, source: &Path, contents: &[u8]) -> Result<()> {
+    let source = Self::key(source)?;
+    self.entries.insert(source, Entry::Allow(hash(contents)));
+    Ok(())
+  }
+
+  pub fn deny(&mut self, source: &Path) -> Result<()> {
+    let source = Self::key(source)?;
+    self.entries.insert(source, Entry::Deny);
+    Ok(())
+  }
+
+  /// Whether `source` may be loaded: `true` if it was allowed with these
+  /// `contents`, `false` if it was denied. Fails if the user hasn't decided
+  /// yet, or if it changed since it was allowed.
+  pub fn check(&self, source: &Path, contents: &[u8]) -> Result<bool> {
+    let key = Self::key(source)?;
+    let display = key.display();
+    match self.entries.get(&key) {
+      Some(Entry::Allow(allowed)) if *allowed == hash(contents) => Ok(true),
+      Some(Entry::Allow(_)) => bail!(
+        "{display} changed since it was allowed, review it and run `abbr allow {display}`"
+      ),
+      Some(Entry::Deny) => Ok(false),
+      None => bail!(
+        "{display} is not trusted, review it and run `abbr allow {display}`"
+      ),
+    }
+  }
+
+  fn key(source: &Path) -> Result<PathBuf> {
+    let source = fs::canonicalize(source).unwrap_or_else(|_| source.to_owned());
+    ensure!(
+      source.to_str().is_some_and(|s| !s.contains(['\r', '\n'])),
+      "unsupported path: {}",
+      source.display()
+    );
+    Ok(source)
+  }
+}
+
+/// Whether the file is outside the user's own data directory, where someone
+/// else may have written it, and so has to be trusted before it is loaded.
+pub fn is_external(path: &Path) -> bool {
+  let Some(home) = config::home_data_dir() else {
+    return true;
+  };
+  let home = fs::canonicalize(&home).unwrap_or(home);
+  let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_owned());
+  !path.starts_with(home)
+}
+
+fn hash(contents: &[u8]) -> String {
+  Sha256::digest(contents).iter().fold(String::new(), |mut hash, byte| {
+    _ = write!(hash, "{byte:02x}");
+    hash
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::trust::Trust;
+  use std::fs;
+
+  #[test]
+  fn test_check() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("project with spaces.toml");
+    fs::write(&source, "").unwrap();
+
+    let mut trust = Trust::open_dir(dir.path()).unwrap();
+    let err = trust.check(&source, b"v1").unwrap_err();
+    assert!(err.to_string().contains("is not trusted"));
+
+    trust.allow(&source, b"v1").unwrap();
+    trust.save().unwrap();
+    let mut trust = Trust::open_dir(dir.path()).unwrap();
+    assert!(trust.check(&source, b"v1").unwrap());
+    let err = trust.check(&source, b"v2").unwrap_err();
+    assert!(err.to_string().contains("changed since it was allowed"));
+
+    trust.deny(&source).unwrap();
+    trust.save().unwrap();
+    let trust = Trust::open_dir(dir.path()).unwrap();
+    assert!(!trust.check(&source, b"v1").unwrap());
+  }
+}
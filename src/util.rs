@@ -1,4 +1,5 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use std::borrow::Cow;
 use std::fs::{File, OpenOptions};
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -6,6 +7,8 @@ use std::{fs, io};
 
 pub const ALIAS_DELIMITER: char = '=';
 pub const PARAM_DELIMITER: char = '/';
+pub const SCOPE_DELIMITER: char = '.';
+const ESCAPE: char = '\\';
 
 pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<()> {
   let path = path.as_ref();
@@ -112,8 +115,267 @@ pub fn alias_to_pair(alias: &str) -> Result<(&str, &str)> {
     .ok_or(anyhow!("invalid alias format, must be `<origin>=<alias>`"))
 }
 
-pub fn param_to_pair(param: &str) -> Option<(&str, usize)> {
-  param.split_once(PARAM_DELIMITER).and_then(|(param, amount)| {
-    amount.parse::<usize>().ok().map(|amount| (param, amount))
-  })
+/// Splits a parameter alias into the words it is scoped to, if any, and its
+/// name, e.g. `compose.l` into `compose` and `l`. A dot escaped as `\.`, as in
+/// `main\.rs`, doesn't start a scope, see `unescape_scope`.
+pub fn param_to_scope(alias: &str) -> (Option<&str>, &str) {
+  let dot = scope_delimiters(alias).last();
+  match dot {
+    Some(idx) if idx > 0 && idx + 1 < alias.len() => {
+      (Some(&alias[..idx]), &alias[idx + 1..])
+    }
+    _ => (None, alias),
+  }
+}
+
+/// Splits the scope of a parameter alias into its words, unescaped.
+pub fn scope_words(scope: &str) -> Vec<Cow<'_, str>> {
+  let mut words = Vec::new();
+  let mut start = 0;
+  for idx in scope_delimiters(scope) {
+    words.push(unescape_scope(&scope[start..idx]));
+    start = idx + 1;
+  }
+  words.push(unescape_scope(&scope[start..]));
+  words
+}
+
+/// Turns each `\.` into a literal dot.
+pub fn unescape_scope(word: &str) -> Cow<'_, str> {
+  let escaped = format!("{ESCAPE}{SCOPE_DELIMITER}");
+  if word.contains(&escaped) {
+    Cow::Owned(word.replace(&escaped, &SCOPE_DELIMITER.to_string()))
+  } else {
+    Cow::Borrowed(word)
+  }
+}
+
+/// Positions of the dots that aren't escaped.
+fn scope_delimiters(alias: &str) -> impl Iterator<Item = usize> + '_ {
+  alias
+    .match_indices(SCOPE_DELIMITER)
+    .map(|(idx, _)| idx)
+    .filter(|idx| !alias[..*idx].ends_with(ESCAPE))
+}
+
+/// How many of the following arguments a `param/N` group passes through.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Amount {
+  Count(usize),
+  /// All of them, written `param/*`.
+  Rest,
+}
+
+pub fn param_to_pair(param: &str) -> Option<(&str, Amount)> {
+  let (param, amount) = param.rsplit_once(PARAM_DELIMITER)?;
+  // `param\/N` is escaped, see `unescape_param`.
+  if param.ends_with(ESCAPE) {
+    return None;
+  }
+  let amount = match amount {
+    "*" => Amount::Rest,
+    // Unlike `parse`, only accept plain digits, without a sign.
+    amount if amount.bytes().all(|b| b.is_ascii_digit()) => {
+      Amount::Count(amount.parse().ok()?)
+    }
+    _ => return None,
+  };
+  Some((param, amount))
+}
+
+/// Turns `param\/N` into the literal argument `param/N`, which would
+/// otherwise start a group.
+pub fn unescape_param(param: &str) -> Option<String> {
+  let (escaped, amount) = param.rsplit_once(PARAM_DELIMITER)?;
+  let param = escaped.strip_suffix(ESCAPE)?;
+  let literal = format!("{param}{PARAM_DELIMITER}{amount}");
+  param_to_pair(&literal).is_some().then_some(literal)
+}
+
+/// Finds the candidates closest to `target` by edit distance that are close
+/// enough to be a likely typo. Several are returned when they are equally
+/// close, in the order they were given.
+pub fn closest<'a>(
+  target: &str,
+  candidates: impl IntoIterator<Item = &'a str>,
+) -> Vec<&'a str> {
+  let mut best_distance = (target.chars().count() / 3).max(1);
+  let mut best = Vec::new();
+  for candidate in candidates {
+    let distance = distance(target, candidate);
+    if distance < best_distance {
+      best_distance = distance;
+      best.clear();
+    }
+    if distance == best_distance && !best.contains(&candidate) {
+      best.push(candidate);
+    }
+  }
+  best
+}
+
+/// Suggests the `closest` aliases after a "not found" error, if any.
+pub fn did_you_mean(closest: &[&str]) -> String {
+  let mut hint = String::new();
+  for (idx, alias) in closest.iter().enumerate() {
+    hint += match idx {
+      0 => ", did you mean ",
+      _ if idx + 1 == closest.len() => " or ",
+      _ => ", ",
+    };
+    hint += &format!("`{alias}`");
+  }
+  if !hint.is_empty() {
+    hint.push('?');
+  }
+  hint
+}
+
+/// Levenshtein distance between two strings, counted in chars.
+fn distance(a: &str, b: &str) -> usize {
+  let b = b.chars().collect::<Vec<_>>();
+  let mut row = (0..=b.len()).collect::<Vec<_>>();
+  for (i, ca) in a.chars().enumerate() {
+    let mut diagonal = row[0];
+    row[0] = i + 1;
+    for (j, cb) in b.iter().enumerate() {
+      let substitution = diagonal + usize::from(ca != *cb);
+      diagonal = row[j + 1];
+      row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+    }
+  }
+  row[b.len()]
+}
+
+/// Splits a stored command into words the way a POSIX shell would, honoring
+/// single quotes, double quotes and backslash escapes.
+pub fn split_words(command: &str) -> Result<Vec<String>> {
+  let mut words = Vec::new();
+  let mut word = String::new();
+  // Distinguishes an empty quoted word (`''`) from no word at all.
+  let mut in_word = false;
+  let mut chars = command.chars();
+
+  while let Some(c) = chars.next() {
+    match c {
+      c if c.is_whitespace() => {
+        if in_word {
+          words.push(std::mem::take(&mut word));
+          in_word = false;
+        }
+      }
+      '\'' => {
+        in_word = true;
+        loop {
+          match chars.next() {
+            Some('\'') => break,
+            Some(c) => word.push(c),
+            None => bail!("unterminated single quote in `{command}`"),
+          }
+        }
+      }
+      '"' => {
+        in_word = true;
+        loop {
+          match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+              Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+              Some(c) => {
+                word.push('\\');
+                word.push(c);
+              }
+              None => bail!("unterminated double quote in `{command}`"),
+            },
+            Some(c) => word.push(c),
+            None => bail!("unterminated double quote in `{command}`"),
+          }
+        }
+      }
+      '\\' => {
+        in_word = true;
+        if let Some(c) = chars.next() {
+          word.push(c);
+        }
+      }
+      c => {
+        in_word = true;
+        word.push(c);
+      }
+    }
+  }
+  if in_word {
+    words.push(word);
+  }
+
+  Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::util::{
+    closest, did_you_mean, param_to_pair, param_to_scope, scope_words,
+    split_words, unescape_param, unescape_scope, Amount,
+  };
+
+  #[test]
+  fn test_split_words() {
+    assert_eq!(
+      split_words(r#"git  commit -m 'a b' "c \"d\"" e\ f ''"#).unwrap(),
+      vec!["git", "commit", "-m", "a b", r#"c "d""#, "e f", ""]
+    );
+    assert!(split_words("echo 'unterminated").is_err());
+  }
+
+  #[test]
+  fn test_closest() {
+    let aliases = ["dk", "gs", "gtd", "ca"];
+
+    assert_eq!(closest("dks", aliases), ["dk"]);
+    assert_eq!(closest("gtdd", aliases), ["gtd"]);
+    assert!(closest("cargo", aliases).is_empty());
+  }
+
+  #[test]
+  fn test_closest_tie() {
+    let aliases = ["gs", "gd", "gs", "dk"];
+
+    assert_eq!(closest("gx", aliases), ["gs", "gd"]);
+    assert_eq!(closest("gs", aliases), ["gs"]);
+    assert_eq!(
+      did_you_mean(&closest("gx", aliases)),
+      ", did you mean `gs` or `gd`?"
+    );
+    assert_eq!(
+      did_you_mean(&["a", "b", "c"]),
+      ", did you mean `a`, `b` or `c`?"
+    );
+    assert_eq!(did_you_mean(&[]), "");
+  }
+
+  #[test]
+  fn test_param_to_scope() {
+    assert_eq!(param_to_scope("compose.logs.l"), (Some("compose.logs"), "l"));
+    assert_eq!(param_to_scope(".env"), (None, ".env"));
+    assert_eq!(param_to_scope(r"main\.rs"), (None, r"main\.rs"));
+    assert_eq!(param_to_scope(r"cp.main\.rs"), (Some("cp"), r"main\.rs"));
+
+    assert_eq!(scope_words(r"a.b\.c"), vec!["a", "b.c"]);
+    assert_eq!(unescape_scope(r"main\.rs"), "main.rs");
+  }
+
+  #[test]
+  fn test_param_to_pair() {
+    assert_eq!(param_to_pair("a/3"), Some(("a", Amount::Count(3))));
+    assert_eq!(param_to_pair("src/lib/*"), Some(("src/lib", Amount::Rest)));
+    assert_eq!(param_to_pair("src/main.rs"), None);
+    assert_eq!(param_to_pair(r"a\/3"), None);
+    assert_eq!(param_to_pair("a/+3"), None);
+
+    assert_eq!(unescape_param(r"a\/3").as_deref(), Some("a/3"));
+    assert_eq!(unescape_param(r"a\/*").as_deref(), Some("a/*"));
+    assert_eq!(unescape_param(r"a\/b"), None);
+    assert_eq!(unescape_param("a/3"), None);
+  }
 }
@@ -0,0 +1,28 @@
+use std::path::Path;
+use std::process::{Command, Output};
+
+pub fn command(data_dir: &Path, args: &[&str]) -> Command {
+  let mut command = Command::new(env!("CARGO_BIN_EXE_abbr"));
+  // Keep the configuration and the user's own data directory, where trusted
+  // files are recorded, out of the real home directory.
+  command
+    .args(args)
+    .env("_ABBR_DATA_DIR", data_dir)
+    .env("HOME", data_dir.join("home"))
+    .env_remove("XDG_CONFIG_HOME")
+    .env_remove("XDG_DATA_HOME");
+  command
+}
+
+pub fn abbr(data_dir: &Path, args: &[&str]) -> Output {
+  command(data_dir, args).output().unwrap()
+}
+
+pub fn stdout(output: Output) -> String {
+  assert!(
+    output.status.success(),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  String::from_utf8(output.stdout).unwrap()
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::{abbr, command, stdout};
+
+#[test]
+fn test_config_set_get() {
+  let dir = tempfile::tempdir().unwrap();
+
+  assert_eq!(stdout(abbr(dir.path(), &["config", "get", "alias"])), "ab\n");
+  stdout(abbr(dir.path(), &["config", "set", "alias", "aa"]));
+  assert_eq!(stdout(abbr(dir.path(), &["config", "get", "alias"])), "aa\n");
+  let init = stdout(abbr(dir.path(), &["init", "bash"]));
+  assert!(init.contains("aa()"), "{init}");
+
+  let output = abbr(dir.path(), &["config", "set", "fuzzy", "maybe"]);
+  assert!(!output.status.success());
+
+  stdout(abbr(dir.path(), &["config", "unset", "alias"]));
+  assert_eq!(stdout(abbr(dir.path(), &["config", "get", "alias"])), "ab\n");
+}
+
+#[test]
+fn test_config_list() {
+  let dir = tempfile::tempdir().unwrap();
+  stdout(abbr(dir.path(), &["config", "set", "quoting", "raw"]));
+
+  let output = command(dir.path(), &["config", "list"])
+    .env("_ABBR_FUZZY", "true")
+    .output()
+    .unwrap();
+  assert_eq!(
+    stdout(output),
+    format!(
+      "alias = ab\nquoting = raw\nfuzzy = true  # from _ABBR_FUZZY\n\
+       data-dir = {}  # from _ABBR_DATA_DIR\nprofile = default\n",
+      dir.path().display()
+    )
+  );
+}
+
+#[test]
+fn test_config_query() {
+  let dir = tempfile::tempdir().unwrap();
+  stdout(abbr(dir.path(), &["new", "docker=dk"]));
+
+  let output = abbr(dir.path(), &["query", "dks", "ps"]);
+  assert!(!output.status.success());
+
+  stdout(abbr(dir.path(), &["config", "set", "fuzzy", "true"]));
+  let output = abbr(dir.path(), &["query", "dks", "$HOME"]);
+  assert_eq!(
+    String::from_utf8_lossy(&output.stderr),
+    "abbr: expanding `dk` for `dks`\n"
+  );
+  assert_eq!(stdout(output), r"docker \$HOME");
+
+  stdout(abbr(dir.path(), &["config", "set", "quoting", "raw"]));
+  assert_eq!(
+    stdout(abbr(dir.path(), &["query", "dk", "$HOME"])),
+    "docker $HOME"
+  );
+}
+
+#[test]
+fn test_config_query_fuzzy_tie() {
+  let dir = tempfile::tempdir().unwrap();
+  stdout(abbr(dir.path(), &["new", "git status=gs"]));
+  stdout(abbr(dir.path(), &["new", "git diff=gd"]));
+  stdout(abbr(dir.path(), &["config", "set", "fuzzy", "true"]));
+
+  let output = abbr(dir.path(), &["query", "gx"]);
+  assert!(!output.status.success());
+  assert!(output.stdout.is_empty());
+  assert_eq!(
+    String::from_utf8_lossy(&output.stderr),
+    "abbr: alias `gx` not found, did you mean `gd` or `gs`?\n"
+  );
+}
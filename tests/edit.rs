@@ -0,0 +1,85 @@
+mod common;
+
+use common::{abbr, stdout};
+use tempfile::TempDir;
+
+fn setup() -> TempDir {
+  let dir = tempfile::tempdir().unwrap();
+  stdout(abbr(dir.path(), &["new", "docker=dk", "compose=cmp", "ls=l"]));
+  dir
+}
+
+#[test]
+fn test_edit_add_persists() {
+  let dir = setup();
+
+  let output = abbr(dir.path(), &["edit", "dk", "add", "image=i", "list=l"]);
+  assert_eq!(
+    stdout(output),
+    "added     i: image\nreplaced  l: list (was: ls)\n"
+  );
+
+  let output = abbr(dir.path(), &["query", "dk", "i", "l"]);
+  assert_eq!(stdout(output), "docker image list");
+}
+
+#[test]
+fn test_new_reports_scoped_params() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let args = ["new", "docker=dk", "Dockerfile.dev=df.dev", "list=df\\.l"];
+  assert_eq!(
+    stdout(abbr(dir.path(), &args)),
+    "added     df.dev: Dockerfile.dev (scoped to `df`)\n\
+     added     df\\.l: list\n"
+  );
+
+  let output = abbr(dir.path(), &["new", "docker=dk", "compose=a..c"]);
+  assert!(!output.status.success());
+  let output = abbr(dir.path(), &["query", "dk", "df.l"]);
+  assert_eq!(stdout(output), "docker list");
+}
+
+#[test]
+fn test_edit_rmv_persists() {
+  let dir = setup();
+
+  let output = abbr(dir.path(), &["edit", "dk", "rmv", "cmp", "x"]);
+  assert_eq!(stdout(output), "removed   cmp\nnot found x\n");
+
+  let output = abbr(dir.path(), &["query", "dk", "cmp"]);
+  assert_eq!(stdout(output), "docker cmp");
+}
+
+#[test]
+fn test_edit_def_and_del_persist() {
+  let dir = setup();
+
+  let output = abbr(dir.path(), &["edit", "dk", "def", "ps"]);
+  assert_eq!(stdout(output), "default arguments: ps\n");
+  let output = abbr(dir.path(), &["query", "dk"]);
+  assert_eq!(stdout(output), "docker ps");
+
+  let output = abbr(dir.path(), &["edit", "dk", "del"]);
+  assert_eq!(stdout(output), "deleted   dk\n");
+  let output = abbr(dir.path(), &["query", "dk"]);
+  assert!(!output.status.success());
+}
+
+#[test]
+fn test_edit_missing_alias() {
+  let dir = setup();
+
+  let output = abbr(dir.path(), &["edit", "dks", "add", "ps=p"]);
+  assert!(!output.status.success());
+  assert_eq!(
+    String::from_utf8(output.stderr).unwrap(),
+    "abbr: alias `dks` not found, did you mean `dk`?\n"
+  );
+
+  let output = abbr(dir.path(), &["edit", "xyz", "del"]);
+  assert_eq!(
+    String::from_utf8(output.stderr).unwrap(),
+    "abbr: alias `xyz` not found\n"
+  );
+}
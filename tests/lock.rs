@@ -0,0 +1,43 @@
+mod common;
+
+use common::{abbr, command, stdout};
+use std::fs::File;
+
+#[test]
+fn test_concurrent_writers() {
+  const WRITERS: usize = 32;
+  let dir = tempfile::tempdir().unwrap();
+
+  let children = (0..WRITERS)
+    .map(|i| {
+      let alias = format!("echo {i}=e{i}");
+      command(dir.path(), &["new", &alias]).spawn().unwrap()
+    })
+    .collect::<Vec<_>>();
+  for child in children {
+    assert!(child.wait_with_output().unwrap().status.success());
+  }
+
+  let output = stdout(abbr(dir.path(), &["list", "--complete"]));
+  assert_eq!(output.lines().count(), WRITERS);
+}
+
+#[test]
+fn test_readers_do_not_wait() {
+  let dir = tempfile::tempdir().unwrap();
+  stdout(abbr(dir.path(), &["new", "echo=e"]));
+
+  let lock = File::create(dir.path().join("db.lock")).unwrap();
+  lock.lock().unwrap();
+
+  // Whenever the writer gets to the lock, it can't write while the lock is
+  // held here, so what the readers see doesn't depend on timing.
+  let mut writer = command(dir.path(), &["new", "git=g"]).spawn().unwrap();
+  assert_eq!(stdout(abbr(dir.path(), &["query", "e", "hi"])), "echo hi");
+  assert_eq!(stdout(abbr(dir.path(), &["list", "--complete"])), "e\techo\n");
+  assert!(writer.try_wait().unwrap().is_none());
+
+  drop(lock);
+  assert!(writer.wait().unwrap().success());
+  assert_eq!(stdout(abbr(dir.path(), &["query", "g"])), "git");
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::{abbr, command, stdout};
+
+#[test]
+fn test_profile_databases() {
+  let dir = tempfile::tempdir().unwrap();
+  stdout(abbr(dir.path(), &["new", "docker=dk"]));
+
+  let output = abbr(dir.path(), &["profile", "use", "work"]);
+  assert!(!output.status.success());
+  stdout(abbr(dir.path(), &["profile", "create", "work"]));
+  stdout(abbr(dir.path(), &["profile", "use", "work"]));
+  assert_eq!(
+    stdout(abbr(dir.path(), &["profile", "list"])),
+    "  default\n* work\n"
+  );
+
+  assert!(!abbr(dir.path(), &["query", "dk"]).status.success());
+  stdout(abbr(dir.path(), &["new", "kubectl=dk"]));
+  assert_eq!(stdout(abbr(dir.path(), &["query", "dk"])), "kubectl");
+  assert!(dir.path().join("profiles/work").is_dir());
+
+  let output = command(dir.path(), &["query", "dk"])
+    .env("_ABBR_PROFILE", "default")
+    .output()
+    .unwrap();
+  assert_eq!(stdout(output), "docker");
+
+  let output = abbr(dir.path(), &["profile", "delete", "work"]);
+  assert!(!output.status.success());
+  stdout(abbr(dir.path(), &["profile", "use", "default"]));
+  stdout(abbr(dir.path(), &["profile", "delete", "work"]));
+  assert_eq!(stdout(abbr(dir.path(), &["profile", "list"])), "* default\n");
+  assert_eq!(stdout(abbr(dir.path(), &["query", "dk"])), "docker");
+}
+
+#[test]
+fn test_profile_missing() {
+  let dir = tempfile::tempdir().unwrap();
+
+  let output = command(dir.path(), &["query", "dk"])
+    .env("_ABBR_PROFILE", "work")
+    .output()
+    .unwrap();
+  assert!(!output.status.success());
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(stderr.contains("profile `work` does not exist"), "{stderr}");
+
+  for args in [["create", "default"], ["create", "a/b"], ["delete", "work"]] {
+    let output = abbr(dir.path(), &[&["profile"], &args[..]].concat());
+    assert!(!output.status.success(), "{args:?}");
+  }
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use common::{abbr, command, stdout};
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+const PROJECT: &str = "[[records]]\nalias = \"e\"\norigin = \"echo project\"\n";
+
+fn setup() -> (tempfile::TempDir, tempfile::TempDir) {
+  let data_dir = tempfile::tempdir().unwrap();
+  stdout(abbr(data_dir.path(), &["new", "echo global=e"]));
+  stdout(abbr(data_dir.path(), &["new", "git=g"]));
+
+  let project = tempfile::tempdir().unwrap();
+  fs::create_dir_all(project.path().join("services/api")).unwrap();
+  fs::write(project.path().join(".abbr.toml"), PROJECT).unwrap();
+  (data_dir, project)
+}
+
+fn run_in(data_dir: &Path, project: &Path, args: &[&str]) -> Output {
+  let dir = project.join("services/api");
+  command(data_dir, args).current_dir(dir).output().unwrap()
+}
+
+#[test]
+fn test_project_precedence() {
+  let (data_dir, project) = setup();
+  let run =
+    |args: &[&str]| stdout(run_in(data_dir.path(), project.path(), args));
+  assert_eq!(
+    run(&["allow"]),
+    format!("allowed {}\n", project.path().join(".abbr.toml").display())
+  );
+
+  assert_eq!(run(&["query", "e", "hi"]), "echo project hi");
+  assert_eq!(run(&["query", "g"]), "git");
+  let list = run(&["list", "--source"]);
+  let sources = list
+    .lines()
+    .map(|line| line.split_whitespace().collect::<Vec<_>>())
+    .collect::<Vec<_>>();
+  let path = project.path().join(".abbr.toml");
+  let path = path.to_str().unwrap();
+  assert_eq!(
+    sources,
+    vec![vec![path, "e", "echo", "project"], vec!["global", "g", "git"]]
+  );
+
+  // Outside of the project, the global alias is used.
+  assert_eq!(stdout(abbr(data_dir.path(), &["query", "e"])), "echo global");
+}
+
+#[test]
+fn test_project_trust() {
+  let (data_dir, project) = setup();
+  let path = project.path().join(".abbr.toml");
+  let run = |args: &[&str]| run_in(data_dir.path(), project.path(), args);
+
+  // Until the file is allowed, only the global aliases are used.
+  let output = run(&["query", "e"]);
+  let stderr = String::from_utf8(output.stderr.clone()).unwrap();
+  assert_eq!(stdout(output), "echo global");
+  assert_eq!(
+    stderr,
+    format!(
+      "abbr: {0} is not trusted, review it and run `abbr allow {0}`\n",
+      path.display()
+    )
+  );
+
+  stdout(run(&["allow"]));
+  assert_eq!(stdout(run(&["query", "e"])), "echo project");
+  fs::write(&path, PROJECT.replace("project", "changed")).unwrap();
+  let output = run(&["query", "e"]);
+  let stderr = String::from_utf8(output.stderr.clone()).unwrap();
+  assert_eq!(stdout(output), "echo global");
+  assert!(stderr.contains("changed since it was allowed"));
+
+  // Denied files are ignored.
+  stdout(run(&["deny"]));
+  assert_eq!(stdout(run(&["query", "e"])), "echo global");
+}
+
+#[test]
+fn test_project_invalid() {
+  let (data_dir, project) = setup();
+  let path = project.path().join(".abbr.toml");
+  let run = |args: &[&str]| run_in(data_dir.path(), project.path(), args);
+  fs::write(&path, "[[records]]\nalias = \"e\"\n").unwrap();
+  stdout(run(&["allow"]));
+
+  // The global aliases are still used, and the error is reported.
+  let output = run(&["query", "e"]);
+  let stderr = String::from_utf8(output.stderr.clone()).unwrap();
+  assert_eq!(stdout(output), "echo global");
+  assert!(
+    stderr.starts_with(&format!(
+      "abbr: invalid project file, ignoring it: {}",
+      path.display()
+    )),
+    "{stderr}"
+  );
+  assert!(stderr.contains("missing field `origin`"), "{stderr}");
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::{abbr, stdout};
+use std::fs;
+
+#[test]
+fn test_external_database() {
+  // The data directory is outside the default one, so only the changes made
+  // through abbr are trusted.
+  let dir = tempfile::tempdir().unwrap();
+  stdout(abbr(dir.path(), &["new", "echo=e"]));
+  assert_eq!(stdout(abbr(dir.path(), &["query", "e"])), "echo");
+
+  let other = tempfile::tempdir().unwrap();
+  stdout(abbr(other.path(), &["new", "rm -rf=e"]));
+  let db = dir.path().join("db.abb");
+  fs::copy(other.path().join("db.abb"), &db).unwrap();
+  let output = abbr(dir.path(), &["query", "e"]);
+  assert!(!output.status.success());
+  assert!(String::from_utf8(output.stderr)
+    .unwrap()
+    .contains("changed since it was allowed"));
+
+  stdout(abbr(dir.path(), &["allow", db.to_str().unwrap()]));
+  assert_eq!(stdout(abbr(dir.path(), &["query", "e"])), "rm -rf");
+}
+
+#[test]
+fn test_import_trust() {
+  let dir = tempfile::tempdir().unwrap();
+  let file = dir.path().join("aliases");
+  fs::write(&file, "alias e='echo'\n").unwrap();
+  let file = file.to_str().unwrap();
+  let import = ["import", "--from", "bash-aliases", file];
+
+  let output = abbr(dir.path(), &import);
+  assert!(!output.status.success());
+  assert!(String::from_utf8(output.stderr).unwrap().contains("is not trusted"));
+
+  // A dry run shows the file so that it can be reviewed.
+  let output = abbr(dir.path(), &[&import[..], &["--dry-run"]].concat());
+  assert!(stdout(output).starts_with("added     e: echo\n"));
+
+  stdout(abbr(dir.path(), &["allow", file]));
+  assert_eq!(stdout(abbr(dir.path(), &import)), "added     e: echo\n");
+
+  stdout(abbr(dir.path(), &["deny", file]));
+  let output = abbr(dir.path(), &import);
+  assert!(String::from_utf8(output.stderr).unwrap().contains("is denied"));
+}
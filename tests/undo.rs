@@ -0,0 +1,51 @@
+mod common;
+
+use common::{abbr, stdout};
+use std::path::Path;
+
+fn aliases(dir: &Path) -> String {
+  stdout(abbr(dir, &["list"]))
+}
+
+#[test]
+fn test_undo_walks_back() {
+  let dir = tempfile::tempdir().unwrap();
+  for alias in ["a", "b", "c", "d"] {
+    stdout(abbr(dir.path(), &["new", &format!("echo {alias}={alias}")]));
+  }
+
+  stdout(abbr(dir.path(), &["undo"]));
+  stdout(abbr(dir.path(), &["undo"]));
+  assert_eq!(aliases(dir.path()), "a  echo a\nb  echo b\n");
+
+  // Only the state before `b` is left to undo to.
+  let output = abbr(dir.path(), &["undo", "2"]);
+  assert!(!output.status.success());
+  let history = stdout(abbr(dir.path(), &["history"]));
+  let steps = history
+    .lines()
+    .map(|line| line.split_whitespace().next().unwrap())
+    .collect::<Vec<_>>();
+  assert_eq!(steps, ["r2", "r1", "1"]);
+
+  stdout(abbr(dir.path(), &["redo", "2"]));
+  assert_eq!(aliases(dir.path()).lines().count(), 4);
+  let output = abbr(dir.path(), &["redo"]);
+  assert!(!output.status.success());
+}
+
+#[test]
+fn test_undo_after_change() {
+  let dir = tempfile::tempdir().unwrap();
+  for alias in ["a", "b"] {
+    stdout(abbr(dir.path(), &["new", &format!("echo {alias}={alias}")]));
+  }
+
+  stdout(abbr(dir.path(), &["undo"]));
+  stdout(abbr(dir.path(), &["new", "echo c=c"]));
+  // A change starts over from the newest state.
+  let output = abbr(dir.path(), &["redo"]);
+  assert!(!output.status.success());
+  stdout(abbr(dir.path(), &["undo"]));
+  assert_eq!(aliases(dir.path()), "a  echo a\n");
+}